@@ -1,6 +1,7 @@
-use rand::{
-    thread_rng,
-    seq::SliceRandom,
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
 };
 use serenity::{
     prelude::TypeMapKey,
@@ -13,97 +14,904 @@ use serenity::{
             GuildChannel,
             Message,
             PrivateChannel,
-        }
+        },
+        id::{
+            GuildId,
+            MessageId,
+            UserId,
+        },
+        user::User,
     }
 };
 use async_trait::async_trait;
+use tokio::task::JoinHandle;
 use crate::{
     games::{
         game::{
             Game,
+            GameType,
             PlayingGame,
             GameState,
+            Review,
+        },
+        wall::{
+            WallGame,
+            WallProgress,
+            WallStage,
+            GroupResult,
         },
+        media::{
+            ClueImage,
+            ImageUpload,
+            for_clue,
+        },
+        answer::Matcher,
+        clues,
+        traits::{Guess, Rules},
     },
     error::Error,
+    guild::GuildConfig,
+    scores::{
+        ScoreBoard,
+        Scoring,
+        Season,
+    },
+    teams::{
+        Match,
+        Turn,
+        STEAL_POINTS,
+    },
+    session::{
+        Session,
+        SessionKey,
+    },
+    persist::{
+        save_json,
+        load_json,
+        write_atomic,
+        new_game_id,
+    },
+    store::{
+        GameStore,
+        StoreStats,
+        ExhaustedPolicy,
+        pick_from,
+    },
 };
 
 impl TypeMapKey for State {
     type Value = State;
 }
 
+/// Seconds since the unix epoch
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// What you get back from a right guess
+#[derive(Debug, Clone)]
+pub struct Solved {
+    pub answer: String,
+    pub points: u32,
+}
+
 #[derive(Debug)]
 pub struct State {
-    pub main_channel: Option<GuildChannel>,
-    pub games: Vec<Game>,
-    pub playing: Option<PlayingGame>
+    pub guilds: HashMap<GuildId, GuildConfig>,
+    pub guilds_path: Option<PathBuf>,
+    pub store: Box<dyn GameStore>,
+    pub exhausted_policy: ExhaustedPolicy,
+    pub sessions: HashMap<SessionKey, Session>,
+    pub scores: HashMap<GuildId, ScoreBoard>,
+    pub scores_path: Option<PathBuf>,
+    pub snapshot_path: Option<PathBuf>,
+    pub images_path: Option<PathBuf>,
+    /// The tasks moving timed rounds along, one for each channel with a timer going
+    pub timers: HashMap<SessionKey, JoinHandle<()>>,
+    pub matches: HashMap<SessionKey, Match>,
+    pub matches_path: Option<PathBuf>,
+    /// Whoever runs Nancy, they look after the games every guild shares
+    pub owners: HashSet<UserId>,
+    /// The guild whose roles say who can do what in DMs
+    pub home_guild: Option<GuildId>,
 }
 
 impl State {
-    pub fn new() -> State {
+    pub fn new(store: Box<dyn GameStore>) -> State {
         State {
-            main_channel: None,
-            games: vec![],
-            playing: None,
+            guilds: HashMap::new(),
+            guilds_path: None,
+            store,
+            exhausted_policy: ExhaustedPolicy::Recycle,
+            sessions: HashMap::new(),
+            scores: HashMap::new(),
+            scores_path: None,
+            snapshot_path: None,
+            images_path: None,
+            timers: HashMap::new(),
+            matches: HashMap::new(),
+            matches_path: None,
+            owners: HashSet::new(),
+            home_guild: None,
         }
     }
     
-    pub fn with_games(self, games: Vec<Game>) -> State {
+    pub fn with_exhausted_policy(self, exhausted_policy: ExhaustedPolicy) -> State {
         State {
-            games,
+            exhausted_policy,
+            ..self
+        }
+    }
+
+    pub fn with_owners(self, owners: HashSet<UserId>) -> State {
+        State {
+            owners,
+            ..self
+        }
+    }
+
+    pub fn with_home_guild(self, home_guild: Option<GuildId>) -> State {
+        State {
+            home_guild,
+            ..self
+        }
+    }
+
+    pub fn is_owner(&self, user_id: UserId) -> bool {
+        self.owners.contains(&user_id)
+    }
+
+    /// Restores the rounds saved at `snapshot_path` (if there are any) and keeps saving them
+    /// there after every change, so a restart doesn't lose games half way through. Rounds of
+    /// games that don't validate are dropped rather than left to go wrong.
+    pub fn with_snapshot(self, snapshot_path: PathBuf) -> Result<State, Error> {
+        let sessions = load_json::<Vec<Session>>(&snapshot_path)?
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|s| s.playing.game.is_valid())
+            .map(|s| {
+                log::info!("Restored game {} in {} at {:?}", s.playing.game.id, s.key.channel_id, s.playing.state);
+                (s.key, s)
+            })
+            .collect();
+        Ok(State {
+            sessions,
+            snapshot_path: Some(snapshot_path),
+            ..self
+        })
+    }
+
+    fn snapshot(&self) {
+        if let Some(path) = &self.snapshot_path {
+            let sessions = self.sessions.values().collect::<Vec<&Session>>();
+            let _ = save_json(path, &sessions).or_log();
+        }
+    }
+
+    /// Loads the guild settings saved at `guilds_path` and saves them there whenever they change
+    pub fn with_guilds(self, guilds_path: PathBuf) -> Result<State, Error> {
+        let guilds = load_json(&guilds_path)?
+            .unwrap_or_default();
+        Ok(State {
+            guilds,
+            guilds_path: Some(guilds_path),
+            ..self
+        })
+    }
+
+    /// Keeps the pictures for image clues in `images_path`, making it if it's not there yet
+    pub fn with_images(self, images_path: PathBuf) -> Result<State, Error> {
+        std::fs::create_dir_all(&images_path)?;
+        Ok(State {
+            images_path: Some(images_path),
             ..self
+        })
+    }
+
+    /// Saves a picture for an image clue, giving back its file name in the images directory
+    pub fn save_image(&self, extension: &str, contents: &[u8]) -> Result<String, Error> {
+        let dir = self.images_path.as_ref().ok_or(Error::NoImageDir)?;
+        let file = format!("{}.{}", new_game_id(), extension);
+        write_atomic(&dir.join(&file), contents)?;
+        Ok(file)
+    }
+
+    /// Saves the pictures for a game's clues. If one of them can't be saved the ones before it
+    /// are deleted again.
+    pub fn save_images(&self, uploads: &[ImageUpload]) -> Result<Vec<ClueImage>, Error> {
+        let mut images = vec![];
+        for upload in uploads {
+            match self.save_image(&upload.extension, &upload.contents) {
+                Ok(file) => images.push(ClueImage { clue: upload.clue, file, alt: upload.alt.clone() }),
+                Err(e) => {
+                    self.remove_images(&images);
+                    return Err(e);
+                },
+            }
         }
+        Ok(images)
     }
 
-    pub fn add_game(&mut self, game: Game) {
-        self.games.push(game);
+    /// Deletes the pictures for a game that's gone
+    pub fn remove_images(&self, images: &[ClueImage]) {
+        for path in images.iter().filter_map(|i| self.image_path(i)) {
+            let _ = std::fs::remove_file(path)
+                .map_err(Error::from)
+                .or_log();
+        }
     }
 
-    pub fn queue_game(&mut self) -> Result<(), Error> {
-        match &self.playing {
-            Some(PlayingGame { state: GameState::Answered, .. }) | None => { 
-                let mut rng = thread_rng();
-                self.games.shuffle(&mut rng);
-                let game = self.games.pop()
-                    .ok_or(Error::NoGamesLeft)?;
-                self.playing = Some(PlayingGame::new(game));
-                Ok(())
+    /// Where a picture is kept, as long as it's in the images directory and still there
+    fn image_path(&self, image: &ClueImage) -> Option<PathBuf> {
+        let dir = self.images_path.as_ref()?.canonicalize().ok()?;
+        match dir.join(Path::new(&image.file)).canonicalize() {
+            Ok(path) if path.starts_with(&dir) => Some(path),
+            Ok(path) => {
+                log::warn!("Image {} is outside the images directory", path.display());
+                None
             },
+            Err(e) => {
+                log::warn!("Missing image {}: {}", image.file, e);
+                None
+            },
+        }
+    }
+
+    /// Where the pictures for the clues that have just been shown are kept - the newest clue's
+    /// while the round's going, all of them once it's over
+    pub fn images(&self, key: &SessionKey) -> Vec<PathBuf> {
+        let playing = match self.playing(key) {
+            Some(playing) => playing,
+            None => return vec![],
+        };
+        let images = playing.game.game.playable().images();
+        let shown: Vec<&ClueImage> = match playing.state {
+            GameState::Answered => images.iter().collect(),
+            _ => for_clue(images, playing.clues_shown()).collect(),
+        };
+        shown.iter()
+            .filter_map(|i| self.image_path(i))
+            .collect()
+    }
+
+    pub fn guild(&self, guild_id: Option<GuildId>) -> GuildConfig {
+        guild_id
+            .and_then(|g| self.guilds.get(&g))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn update_guild<F>(&mut self, guild_id: GuildId, update: F) -> Result<GuildConfig, Error>
+    where
+        F: FnOnce(&mut GuildConfig) -> Result<(), Error>
+    {
+        let mut config = self.guild(Some(guild_id));
+        update(&mut config)?;
+        self.guilds.insert(guild_id, config.clone());
+        if let Some(path) = &self.guilds_path {
+            save_json(path, &self.guilds)?;
+        }
+        Ok(config)
+    }
+
+    /// Loads the score tables saved at `scores_path` and saves them there whenever someone scores
+    pub fn with_scores(self, scores_path: PathBuf) -> Result<State, Error> {
+        let scores = load_json(&scores_path)?
+            .unwrap_or_default();
+        Ok(State {
+            scores,
+            scores_path: Some(scores_path),
+            ..self
+        })
+    }
+
+    /// Loads the team matches saved at `matches_path` and saves them there whenever they change
+    pub fn with_matches(self, matches_path: PathBuf) -> Result<State, Error> {
+        let matches = load_json::<Vec<Match>>(&matches_path)?
+            .unwrap_or_default()
+            .into_iter()
+            .map(|m| (m.key, m))
+            .collect();
+        Ok(State {
+            matches,
+            matches_path: Some(matches_path),
+            ..self
+        })
+    }
+
+    fn save_matches(&self) -> Result<(), Error> {
+        match &self.matches_path {
+            Some(path) => save_json(path, &self.matches.values().collect::<Vec<&Match>>()),
+            None => Ok(()),
+        }
+    }
+
+    fn save_scores(&self) -> Result<(), Error> {
+        match &self.scores_path {
+            Some(path) => save_json(path, &self.scores),
+            None => Ok(()),
+        }
+    }
+
+    pub fn award(&mut self, guild_id: GuildId, user_id: UserId, name: &str, points: u32, clues_shown: usize) -> Result<(), Error> {
+        self.scores
+            .entry(guild_id)
+            .or_insert_with(ScoreBoard::default)
+            .award(user_id, name, points, clues_shown);
+        self.save_scores()
+    }
+
+    /// Points that aren't for solving a whole round, see `ScoreBoard::add_points`
+    pub fn add_points(&mut self, guild_id: GuildId, user_id: UserId, name: &str, points: u32) -> Result<(), Error> {
+        self.scores
+            .entry(guild_id)
+            .or_insert_with(ScoreBoard::default)
+            .add_points(user_id, name, points);
+        self.save_scores()
+    }
+
+    pub fn scores(&self, guild_id: GuildId) -> ScoreBoard {
+        self.scores
+            .get(&guild_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Archives the guild's current season and starts a new one, giving back the old one
+    pub fn reset_season(&mut self, guild_id: GuildId) -> Result<Season, Error> {
+        let season = self.scores
+            .entry(guild_id)
+            .or_insert_with(ScoreBoard::default)
+            .reset_season(unix_now())
+            .clone();
+        self.save_scores()?;
+        Ok(season)
+    }
+
+    /// Checks the round is being played in the guild's main channel, if it has one
+    pub fn check_channel(&self, key: &SessionKey) -> Result<(), Error> {
+        match self.guild(key.guild_id).main_channel {
+            Some(c) if c != key.channel_id => Err(Error::WrongChannel(c)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Who submitted the game being played, mentioned or not depending on the guild
+    pub fn submitter(&self, key: &SessionKey) -> Option<String> {
+        let mention = self.guild(key.guild_id).mention_submitters;
+        self.playing(key)
+            .map(|p| p.game.submitter(mention))
+    }
+
+    pub fn playing(&self, key: &SessionKey) -> Option<&PlayingGame> {
+        self.sessions
+            .get(key)
+            .map(|s| &s.playing)
+    }
+
+    /// Updates the round in this channel, keeping track of the message it's shown in
+    fn set_playing(&mut self, key: &SessionKey, playing: PlayingGame) {
+        let message_id = self.round_message(key);
+        self.sessions.insert(*key, Session {
+            message_id,
+            ..Session::new(*key, playing)
+        });
+        self.snapshot();
+    }
+
+    /// Ends the round in this channel without revealing the answer, giving back the game so it
+    /// can be put back with `put_back`
+    pub fn abandon(&mut self, key: &SessionKey) -> Result<Game, Error> {
+        let game = match self.playing(key) {
+            Some(p) if !matches!(p.state, GameState::Answered) => p.game.clone(),
+            _ => return Err(Error::NoGamePlaying),
+        };
+        self.stop_timer(key);
+        self.sessions.remove(key);
+        self.snapshot();
+        // In a match the same team gets to pick again
+        if let Some(m) = self.matches.get_mut(key).filter(|m| m.started) {
+            m.turn = Turn::Picking;
+            let _ = self.save_matches().or_log();
+        }
+        Ok(game)
+    }
+
+    /// Lets an abandoned game be played again as if its round never happened
+    pub fn put_back(&mut self, game: &Game) -> Result<Game, Error> {
+        self.store.unmark_played(&game.id)
+    }
+
+    /// Rounds that have started and haven't been answered yet
+    pub fn rounds_going(&self) -> Vec<SessionKey> {
+        self.sessions
+            .values()
+            .filter(|s| !matches!(s.playing.state, GameState::Ready | GameState::Answered))
+            .map(|s| s.key)
+            .collect()
+    }
+
+    /// Rounds that have gone longer than their guild's idle timeout without anyone doing
+    /// anything. Rounds that haven't shown a clue yet have nothing to reveal, so they're left
+    /// out.
+    pub fn idle_rounds(&self, now: u64) -> Vec<SessionKey> {
+        self.sessions
+            .values()
+            .filter(|s| !matches!(s.playing.state, GameState::Ready | GameState::Answered))
+            .filter(|s| match self.guild(s.key.guild_id).idle_minutes {
+                Some(minutes) => now.saturating_sub(s.last_active) > minutes * 60,
+                None => false,
+            })
+            .map(|s| s.key)
+            .collect()
+    }
+
+    /// Keeps hold of the task timing the round in this channel, stopping any that was already
+    /// going. The task stops by itself once the round is over.
+    pub fn set_timer(&mut self, key: &SessionKey, timer: JoinHandle<()>) {
+        if let Some(old) = self.timers.insert(*key, timer) {
+            old.abort();
+        }
+    }
+
+    /// Stops the round timer in this channel, giving back whether there was one going
+    pub fn stop_timer(&mut self, key: &SessionKey) -> bool {
+        let stopped = self.timers
+            .remove(key)
+            .map(|timer| timer.abort())
+            .is_some();
+        stopped && self.playing(key).map(|p| !matches!(p.state, GameState::Answered)).unwrap_or(false)
+    }
+
+    /// The message the round in this channel is shown in
+    pub fn round_message(&self, key: &SessionKey) -> Option<MessageId> {
+        self.sessions
+            .get(key)
+            .and_then(|s| s.message_id)
+    }
+
+    pub fn set_round_message(&mut self, key: &SessionKey, message_id: MessageId) {
+        if let Some(session) = self.sessions.get_mut(key) {
+            session.message_id = Some(message_id);
+            self.snapshot();
+        }
+    }
+
+    pub fn stats(&self) -> Result<StoreStats, Error> {
+        self.store.stats()
+    }
+
+    /// Adds a game, deleting its pictures again if it can't be saved
+    pub fn add_game(&mut self, game: Game) -> Result<Game, Error> {
+        game.validate()?;
+        let images = game.game.playable().images().to_vec();
+        self.store
+            .add(game)
+            .map_err(|e| {
+                self.remove_images(&images);
+                e
+            })
+    }
+
+    /// The games `user` has submitted
+    pub fn games_by(&self, user: &User) -> Result<Vec<Game>, Error> {
+        Ok(self.store
+            .list()?
+            .into_iter()
+            .filter(|g| g.is_submitted_by(user))
+            .collect())
+    }
+
+    /// A game `user` submitted, for anything only the submitter is allowed to do
+    pub fn own_game(&self, id: &str, user: &User) -> Result<Game, Error> {
+        let game = self.store.get(id)?;
+        match game.submitter_id {
+            _ if game.is_submitted_by(user) => Ok(game),
+            Some(_) => Err(Error::NotYourGame(id.to_string())),
+            None => Err(Error::Unclaimed(id.to_string())),
+        }
+    }
+
+    /// Says who submitted a game from before submitters' ids were kept, so they can look after
+    /// it. Games that already belong to someone can't be claimed.
+    pub fn claim_game(&mut self, id: &str, user_id: UserId) -> Result<Game, Error> {
+        let game = self.store.get(id)?;
+        if game.submitter_id.is_some() {
+            return Err(Error::AlreadyClaimed(id.to_string()));
+        }
+        self.store.update(Game {
+            submitter_id: Some(user_id),
+            ..game
+        })
+    }
+
+    /// Saves changes `user` made to one of their games, which puts it back in the moderation
+    /// queue
+    pub fn update_game(&mut self, game: Game, user: &User) -> Result<Game, Error> {
+        self.own_game(&game.id, user)?;
+        game.validate()?;
+        self.store.update(Game {
+            review: Review::Pending,
+            ..game
+        })
+    }
+
+    pub fn game(&self, id: &str) -> Result<Game, Error> {
+        self.store.get(id)
+    }
+
+    /// Games waiting for a moderator
+    pub fn pending_games(&self) -> Result<Vec<Game>, Error> {
+        Ok(self.store
+            .list()?
+            .into_iter()
+            .filter(|g| g.review == Review::Pending)
+            .collect())
+    }
+
+    /// Approves or rejects a game
+    pub fn review_game(&mut self, id: &str, review: Review) -> Result<Game, Error> {
+        let game = self.store.get(id)?;
+        self.store.update(Game {
+            review,
+            ..game
+        })
+    }
+
+    /// Deletes a game along with its pictures
+    pub fn delete_game(&mut self, id: &str, user: &User) -> Result<Game, Error> {
+        self.own_game(id, user)?;
+        let game = self.store.remove(id)?;
+        self.remove_images(game.game.playable().images());
+        Ok(game)
+    }
+
+    pub fn queue_game(&mut self, key: &SessionKey) -> Result<(), Error> {
+        self.queue_game_with(key, self.exhausted_policy)
+    }
+
+    /// Starts a round with a random unplayed game, falling back to `policy` when there
+    /// aren't any. The game stays in the store with its play count bumped.
+    pub fn queue_game_with(&mut self, key: &SessionKey, policy: ExhaustedPolicy) -> Result<(), Error> {
+        if self.team_match(key).map(|m| m.started).unwrap_or(false) {
+            return Err(Error::MatchInProgress);
+        }
+        self.check_finished(key)?;
+        let moderated = self.guild(key.guild_id).moderated;
+        let game = self.store.pick_next(policy, moderated)?;
+        self.start_playing(key, game);
+        Ok(())
+    }
+
+    fn check_finished(&self, key: &SessionKey) -> Result<(), Error> {
+        match self.playing(key) {
+            Some(PlayingGame { state: GameState::Answered, .. }) | None => Ok(()),
             _ => Err(Error::NotFinishedPlayingYet),
         }
     }
 
-    pub fn next_clue(&mut self) -> Result<String, Error> {
-        match &self.playing {
-            Some(PlayingGame { state: GameState::Ready, .. }) |
-                Some(PlayingGame { state: GameState::Clue(_), .. } ) => {
-                    let (clue, playing) = self.playing
-                        .as_ref()
-                        .ok_or(Error::NoGamePlaying)
-                        .and_then(|p| {
-                            let (clue, state) = p.clone().next_clue();
-                            Ok((clue, p.clone().with_state(state)))
-                        })?;
-                    self.playing = Some(playing);
-                    Ok(clue.unwrap_or("".to_string()))
+    fn start_playing(&mut self, key: &SessionKey, game: Game) {
+        let game = self.store
+            .mark_played(&game.id, unix_now())
+            .or_log()
+            .unwrap_or(game);
+        // A new round gets a new message, and a new timer if it's timed
+        self.sessions.remove(key);
+        self.stop_timer(key);
+        self.set_playing(key, PlayingGame::new(game));
+    }
+
+    pub fn team_match(&self, key: &SessionKey) -> Option<&Match> {
+        self.matches.get(key)
+    }
+
+    /// Puts `user_id` in a team for the match in this channel, setting one up if there isn't
+    /// one yet
+    pub fn join_team(&mut self, key: &SessionKey, team: &str, user_id: UserId) -> Result<String, Error> {
+        let joined = self.matches
+            .entry(*key)
+            .or_insert_with(|| Match::new(*key))
+            .join(team, user_id)?
+            .name
+            .clone();
+        self.save_matches()?;
+        Ok(joined)
+    }
+
+    pub fn start_match(&mut self, key: &SessionKey) -> Result<&Match, Error> {
+        self.check_finished(key)?;
+        self.matches
+            .get_mut(key)
+            .ok_or(Error::NoMatch)?
+            .start()?;
+        self.save_matches()?;
+        self.team_match(key).ok_or(Error::NoMatch)
+    }
+
+    pub fn end_match(&mut self, key: &SessionKey) -> Result<Match, Error> {
+        let ended = self.matches.remove(key).ok_or(Error::NoMatch)?;
+        self.save_matches()?;
+        Ok(ended)
+    }
+
+    /// Starts a round with a game picked by the team on turn, they can ask for a link or a
+    /// sequence or leave it to chance. Walls and missing vowels don't have turns so they aren't
+    /// played in matches.
+    pub fn pick_game(&mut self, key: &SessionKey, user_id: UserId, kind: Option<&str>) -> Result<(), Error> {
+        let m = self.team_match(key)
+            .filter(|m| m.started)
+            .ok_or(Error::NoMatch)?;
+        let team = m.team_of(user_id).ok_or(Error::NotInTeam)?;
+        if team != m.on_turn || m.turn != Turn::Picking {
+            return Err(Error::NotYourTurn(m.teams[m.on_turn].name.clone()));
+        }
+        self.check_finished(key)?;
+        let kind = match kind {
+            None => None,
+            Some("link") => Some("link"),
+            Some("sequence") | Some("seq") => Some("sequence"),
+            Some(other) => return Err(Error::ArgError(format!("`{}` should be link or sequence", other))),
+        };
+        let moderated = self.guild(key.guild_id).moderated;
+        let games = self.store
+            .list()?
+            .into_iter()
+            .filter(|g| matches!(g.game, GameType::Link(_) | GameType::Sequence(_)))
+            .filter(|g| kind.map(|k| g.game.kind() == k).unwrap_or(true))
+            .collect::<Vec<Game>>();
+        let game = pick_from(&games, ExhaustedPolicy::Recycle, moderated)?.clone();
+        self.start_playing(key, game);
+        if let Some(m) = self.matches.get_mut(key) {
+            m.turn = Turn::Answering;
+        }
+        self.save_matches()
+    }
+
+    /// A guess in a match. Only the team on turn can answer, and if they get it wrong the other
+    /// team gets one guess to steal it with every clue showing. You get back whether it was
+    /// right and what to say about it.
+    pub fn match_guess(&mut self, key: &SessionKey, user_id: UserId, name: &str, guess: &str) -> Result<(bool, String), Error> {
+        self.touch(key);
+        let m = self.team_match(key)
+            .filter(|m| m.started)
+            .cloned()
+            .ok_or(Error::NoMatch)?;
+        let team = m.answering_team(user_id)?;
+        let playing = self.playing(key)
+            .filter(|p| p.is_guessable())
+            .cloned()
+            .ok_or(Error::NothingToGuess)?;
+        let rules = self.rules(key, &playing.game.game);
+        let right = matches!(playing.guess(guess, &rules), Guess::Solved);
+        match m.turn {
+            Turn::Answering if right => {
+                let clues_shown = playing.clues_shown();
+                let points = Scoring::points(&rules.points, clues_shown);
+                if let Some(guild_id) = key.guild_id {
+                    let _ = self.award(guild_id, user_id, name, points, clues_shown).or_log();
+                }
+                self.team_scored(key, team, points);
+                let answer = self.reveal(key)?;
+                Ok((true, format!("{} got it for {} points for {}!\n\n{}", name, points, m.teams[team].name, answer)))
+            },
+            Turn::Answering => Ok((false, self.open_steal(key)?)),
+            _ if right => {
+                if let Some(guild_id) = key.guild_id {
+                    let _ = self.add_points(guild_id, user_id, name, STEAL_POINTS).or_log();
+                }
+                self.team_scored(key, team, STEAL_POINTS);
+                let answer = self.reveal(key)?;
+                Ok((true, format!("{} stole it for {}!\n\n{}", name, m.teams[team].name, answer)))
+            },
+            _ => Ok((false, format!("No steal either!\n\n{}", self.reveal(key)?))),
+        }
+    }
+
+    /// Shows every clue and hands the round over to the team that isn't on turn
+    fn open_steal(&mut self, key: &SessionKey) -> Result<String, Error> {
+        let playing = self.playing(key).cloned().ok_or(Error::NoGamePlaying)?;
+        let m = self.matches.get_mut(key).ok_or(Error::NoMatch)?;
+        m.open_steal();
+        let stealing = m.teams[m.other()].name.clone();
+        self.save_matches()?;
+        let game = playing.game.game.playable();
+        let clues = clues::numbered(game.clues(), game.clue_count());
+        self.set_playing(key, playing.with_state(GameState::NoCluesLeft));
+        Ok(format!("Over to {} for a steal, here are all the clues\n\n>>> {}", stealing, clues))
+    }
+
+    /// For when a round runs out of time. In a match the team on turn has missed, so the other
+    /// team still gets their steal, anywhere else the answer's revealed.
+    pub fn time_up(&mut self, key: &SessionKey) -> Result<String, Error> {
+        let answering = self.team_match(key)
+            .map(|m| m.started && m.turn == Turn::Answering)
+            .unwrap_or(false);
+        let guessable = self.playing(key).map(PlayingGame::is_guessable).unwrap_or(false);
+        if answering && guessable {
+            self.open_steal(key)
+        } else {
+            self.reveal(key)
+        }
+    }
+
+    /// In a match only the team on turn can see more clues
+    pub fn check_turn(&self, key: &SessionKey, user_id: UserId) -> Result<(), Error> {
+        match self.team_match(key).filter(|m| m.started) {
+            Some(m) => m.check_clue(user_id),
+            None => Ok(()),
+        }
+    }
+
+    /// Someone's done something in the round, so it isn't idle
+    fn touch(&mut self, key: &SessionKey) {
+        if let Some(session) = self.sessions.get_mut(key) {
+            session.last_active = unix_now();
+        }
+    }
+
+    fn team_scored(&mut self, key: &SessionKey, team: usize, points: u32) {
+        if let Some(t) = self.matches.get_mut(key).and_then(|m| m.teams.get_mut(team)) {
+            t.score += points;
+        }
+        let _ = self.save_matches().or_log();
+    }
+
+    /// Once a round in a match is over the other team is on turn, you get back the scores to
+    /// post with the answer
+    fn end_turn(&mut self, key: &SessionKey) -> Option<String> {
+        let m = self.matches
+            .get_mut(key)
+            .filter(|m| m.started && m.turn != Turn::Picking)?;
+        m.next_turn();
+        let scoreboard = m.scoreboard();
+        let _ = self.save_matches().or_log();
+        Some(scoreboard)
+    }
+
+    /// The guild's settings for a round of `game` in this channel
+    fn rules(&self, key: &SessionKey, game: &GameType) -> Rules {
+        let guild = self.guild(key.guild_id);
+        Rules {
+            matcher: Matcher::new(guild.guess_tolerance),
+            points: guild.scoring.table(game).to_vec(),
+            wall_seconds: guild.wall_seconds,
+        }
+    }
+
+    /// Shows the next clue, or in missing vowels gives up on the phrase that's up and shows
+    /// the next one. If that was the last of it the answers are revealed too.
+    pub fn next_clue(&mut self, key: &SessionKey) -> Result<String, Error> {
+        let playing = self.playing(key).cloned().ok_or(Error::NoCluesToShow)?;
+        let rules = self.rules(key, &playing.game.game);
+        let (clue, state) = playing.clone().next_clue(&rules);
+        let clue = clue.ok_or(Error::NoCluesToShow)?;
+        let playing = playing.with_state(state);
+        let over = playing.is_over();
+        self.set_playing(key, playing);
+        if over {
+            Ok(format!("{}\n\n{}", clue, self.reveal(key)?))
+        } else {
+            Ok(clue)
+        }
+    }
+
+    /// Checks a guess against the answer. If it's right the round is over, the guesser gets
+    /// points depending on how many clues were showing, and you get back the same thing
+    /// `reveal` would give you.
+    ///
+    /// Some games are guessed a bit at a time instead, like a wall's connections or the phrases
+    /// in missing vowels. Each right guess gets its points and you get back what the game says
+    /// about it, with the answers revealed after the last one.
+    pub fn guess(&mut self, key: &SessionKey, user_id: UserId, name: &str, guess: &str) -> Result<Option<Solved>, Error> {
+        self.touch(key);
+        let playing = match self.playing(key) {
+            Some(p) if p.is_guessable() => p.clone(),
+            Some(_) => return Err(Error::NothingToGuess),
+            None => return Err(Error::NoGamePlaying),
+        };
+        let rules = self.rules(key, &playing.game.game);
+        match playing.guess(guess, &rules) {
+            Guess::Wrong => Ok(None),
+            Guess::Solved => {
+                let clues_shown = playing.clues_shown();
+                let points = Scoring::points(&rules.points, clues_shown);
+                let answer = self.reveal(key)?;
+                if let Some(guild_id) = key.guild_id {
+                    let _ = self.award(guild_id, user_id, name, points, clues_shown).or_log();
+                }
+                Ok(Some(Solved { answer, points }))
+            },
+            Guess::Progress { state, points, said } => {
+                let playing = playing.with_state(state);
+                let over = playing.is_over();
+                self.set_playing(key, playing);
+                let answer = if over {
+                    format!("{}\n\n{}", said, self.reveal(key)?)
+                } else {
+                    said
+                };
+                if let Some(guild_id) = key.guild_id {
+                    let _ = self.add_points(guild_id, user_id, name, points).or_log();
+                }
+                Ok(Some(Solved { answer, points }))
+            },
+        }
+    }
+
+    /// The wall being played and how far the players have got with it
+    fn wall(&self, key: &SessionKey) -> Result<(PlayingGame, WallGame, WallProgress), Error> {
+        let playing = self.playing(key).ok_or(Error::NoGamePlaying)?;
+        match (&playing.game.game, &playing.state) {
+            (GameType::Wall(wall), GameState::Wall(progress)) =>
+                Ok((playing.clone(), wall.clone(), progress.clone())),
+            _ => Err(Error::NothingToGuess),
+        }
+    }
+
+    /// Puts forward four clues as a group. Each group found is a point, the wall freezes if
+    /// the players run out of lives or time, and you get back what happened and the wall as
+    /// it stands.
+    pub fn submit_group(&mut self, key: &SessionKey, user_id: UserId, name: &str, clues: &[String]) -> Result<(GroupResult, String), Error> {
+        let matcher = Matcher::new(self.guild(key.guild_id).guess_tolerance);
+        let (playing, WallGame::Text(wall), mut progress) = self.wall(key)?;
+        if progress.stage != WallStage::Solving {
+            return Err(Error::ArgError("The groups have all been found, name the connections with `!guess`".to_string()));
+        }
+        let result = if progress.deadline.map(|d| unix_now() >= d).unwrap_or(false) {
+            progress.freeze();
+            GroupResult::Frozen
+        } else {
+            let tiles = progress.find_tiles(&wall, clues, &matcher)?;
+            let before = progress.found.len();
+            let result = progress.submit_group(&tiles);
+            let points = (progress.found.len() - before) as u32;
+            if let (true, Some(guild_id)) = (points > 0, key.guild_id) {
+                let _ = self.add_points(guild_id, user_id, name, points).or_log();
+            }
+            result
+        };
+        let rendered = wall.render(&progress);
+        self.set_playing(key, playing.with_state(GameState::Wall(progress)));
+        Ok((result, rendered))
+    }
+
+    /// Freezes the wall if its timer has run out, giving back the frozen wall. Nothing
+    /// happens if the groups were all found in time or a different round has started.
+    pub fn wall_time_up(&mut self, key: &SessionKey, deadline: u64) -> Option<String> {
+        match self.wall(key) {
+            Ok((playing, WallGame::Text(wall), mut progress))
+                if progress.stage == WallStage::Solving && progress.deadline == Some(deadline) => {
+                    progress.freeze();
+                    let rendered = wall.render(&progress);
+                    self.set_playing(key, playing.with_state(GameState::Wall(progress)));
+                    Some(rendered)
                 },
-            _ => Err(Error::NoCluesToShow),
-        }
-    }
-
-    pub fn reveal(&mut self) -> Result<String, Error> {
-        match &self.playing {
-            Some(PlayingGame { state: GameState::Clue(_), .. }) |
-                Some(PlayingGame { state: GameState::NoCluesLeft, ..}) => {
-                    let (answer, playing) = self.playing
-                        .as_ref()
-                        .ok_or(Error::NoGamePlaying)
-                        .and_then(|p| {
-                            let (answer, state) = p.clone().reveal();
-                            Ok((answer, p.clone().with_state(state)))
-                        })?;
-                    self.playing = Some(playing);
-                    Ok(answer)
+            _ => None,
+        }
+    }
+
+    /// When the wall being played runs out of time, if it has a timer
+    pub fn wall_deadline(&self, key: &SessionKey) -> Option<u64> {
+        match self.playing(key) {
+            Some(PlayingGame { state: GameState::Wall(progress), .. }) => progress.deadline,
+            _ => None,
+        }
+    }
+
+    pub fn reveal(&mut self, key: &SessionKey) -> Result<String, Error> {
+        let mention = self.guild(key.guild_id).mention_submitters;
+        match self.playing(key) {
+            Some(p) if !matches!(p.state, GameState::Ready | GameState::Answered) => {
+                let (answer, state) = p.clone().reveal(mention);
+                let playing = p.clone().with_state(state);
+                self.set_playing(key, playing);
+                Ok(match self.end_turn(key) {
+                    Some(scoreboard) => format!("{}\n\n{}", answer, scoreboard),
+                    None => answer,
+                })
             },
             _ => Err(Error::NothingToReveal)
         }
@@ -260,6 +1068,8 @@ pub struct ResponseOk<'a> {
     pub dm_channel: Option<&'a PrivateChannel>,
     pub react: Option<char>,
     pub content: Option<String>,
+    pub files: Vec<PathBuf>,
+    pub edit: Option<MessageId>,
 }
 
 impl<'a> ResponseOk<'a> {
@@ -271,6 +1081,8 @@ impl<'a> ResponseOk<'a> {
             dm_channel: None,
             react: None,
             content: None,
+            files: vec![],
+            edit: None,
         }
     }
 
@@ -301,37 +1113,86 @@ impl<'a> ResponseOk<'a> {
             ..self
         }
     }
+
+    /// Attaches files (like the pictures for image clues) to the content
+    pub fn with_files(self, files: Vec<PathBuf>) -> ResponseOk<'a> {
+        ResponseOk{
+            files,
+            ..self
+        }
+    }
+
+    /// Puts the content in an earlier message in the same channel instead of posting a new one,
+    /// if there is one. Any files still go in a new message, replying to the edited one.
+    pub fn with_edit(self, edit: Option<MessageId>) -> ResponseOk<'a> {
+        ResponseOk{
+            edit,
+            ..self
+        }
+    }
 }
 
 #[async_trait]
 impl<'a> Respondable for ResponseOk<'a> {
     async fn send(self) -> CommandResult {
+        self.post().await?;
+        Ok(())
+    }
+}
+
+impl<'a> ResponseOk<'a> {
+    /// Sends the response, giving back the message that was posted if there was one (the one in
+    /// the channel, if it went to a DM as well)
+    pub async fn post(self) -> serenity::Result<Option<Message>> {
         if let Some(r) = self.react {
             self.message.react(self.context, r).await?;
         }
-        if let Some(text) = self.content {
-            match (self.channel, self.dm_channel) {
-                (Some(chan), None) => {
-                    chan.send_message(self.context, |m| m.content(&text))
-                        .await
+        let text = match self.content {
+            Some(text) => text,
+            None => return Ok(None),
+        };
+        let files = &self.files;
+        if let Some(id) = self.edit {
+            let channel_id = self.message.channel_id;
+            match channel_id.edit_message(self.context, id, |m| m.content(&text)).await {
+                Ok(edited) => {
+                    if !files.is_empty() {
+                        channel_id.send_message(self.context, |m| m.reference_message(&edited).add_files(files))
+                            .await?;
+                    }
+                    return Ok(Some(edited));
                 },
-                (None, Some(dm_chan)) => {
-                    dm_chan.send_message(self.context, |m| m.content(&text))
-                        .await
-                },
-                (Some(chan), Some(dm_chan)) => {
-                    chan.send_message(self.context, |m| m.content(&text))
-                        .await?;
-                    dm_chan.send_message(self.context, |m| m.content(&text))
-                        .await
-                },
-                (None, None) => {
-                    self.message.reply(self.context, text)
-                        .await
-                }
-            }?;
+                // Most likely someone deleted it, so start a new one
+                Err(e) => log::warn!("Couldn't edit message {}: {}", id, e),
+            }
         }
-        Ok(())
+        let posted = match (self.channel, self.dm_channel) {
+            (Some(chan), None) => {
+                chan.send_message(self.context, |m| m.content(&text).add_files(files))
+                    .await
+            },
+            (None, Some(dm_chan)) => {
+                dm_chan.send_message(self.context, |m| m.content(&text).add_files(files))
+                    .await
+            },
+            (Some(chan), Some(dm_chan)) => {
+                let posted = chan.send_message(self.context, |m| m.content(&text).add_files(files))
+                    .await?;
+                dm_chan.send_message(self.context, |m| m.content(&text).add_files(files))
+                    .await?;
+                Ok(posted)
+            },
+            (None, None) if files.is_empty() => {
+                self.message.reply(self.context, text)
+                    .await
+            },
+            (None, None) => {
+                self.message.channel_id
+                    .send_message(self.context, |m| m.content(&text).reference_message(self.message).add_files(files))
+                    .await
+            },
+        }?;
+        Ok(Some(posted))
     }
 }
 
@@ -1,17 +1,42 @@
 use std::fmt::{Formatter, Display, self};
-use crate::data::ResponseErr;
+use serenity::model::id::ChannelId;
+use crate::{
+    data::ResponseErr,
+    guild::Role,
+};
 
 #[derive(Debug, Clone)]
 pub enum Error {
     NoState,
     NoMainChannel,
+    WrongChannel(ChannelId),
+    NotInGuild,
     NoGamesLeft,
+    AllGamesPlayed,
     NoGamePlaying,
+    NoSuchGame(String),
     NotFinishedPlayingYet,
     NoCluesToShow,
     NothingToReveal,
+    NothingToGuess,
     ArgError(String),
     Serde(String),
+    Io(String),
+    Database(String),
+    NoImageDir,
+    Cancelled,
+    TimedOut,
+    NotYourGame(String),
+    Unclaimed(String),
+    AlreadyClaimed(String),
+    OwnersOnly,
+    NotAllowed(Role),
+    NoMatch,
+    MatchStarted,
+    MatchInProgress,
+    TeamsNotReady,
+    NotInTeam,
+    NotYourTurn(String),
 }
 
 impl Display for Error {
@@ -21,12 +46,24 @@ impl Display for Error {
                 write!(f, "No state object in context"),
             Error::NoMainChannel => 
                 write!(f, "No main channel set"),
+            Error::NotInGuild =>
+                write!(f, "That only works in a server"),
+            Error::WrongChannel(c) =>
+                write!(f, "Games are played over in <#{}>, come join us there", c),
             Error::Serde(e) =>
                 write!(f, "{}", e),
+            Error::NoSuchGame(id) =>
+                write!(f, "There's no game with id `{}`", id),
+            Error::Database(e) =>
+                write!(f, "Database error: {}", e),
+            Error::Io(e) =>
+                write!(f, "Couldn't save or load: {}", e),
             Error::NoGamePlaying =>
                 write!(f, "A game hasn't been queued"),
             Error::NoGamesLeft => 
                 write!(f, "There are no games left, try adding some more"),
+            Error::AllGamesPlayed =>
+                write!(f, "Every game has been played, add some more or ask an admin to `!recycle` an old one"),
             Error::ArgError(s) =>
                 write!(f, "Couldn't parse an argument: {}", s),
             Error::NotFinishedPlayingYet =>
@@ -35,6 +72,36 @@ impl Display for Error {
                 write!(f, "No clues to show"),
             Error::NothingToReveal => 
                 write!(f, "Nothing to reveal"),
+            Error::NothingToGuess =>
+                write!(f, "There's nothing to guess at right now"),
+            Error::NoImageDir =>
+                write!(f, "Nowhere to keep images, so image clues can't be added"),
+            Error::Cancelled =>
+                write!(f, "Cancelled, nothing was added"),
+            Error::NoMatch =>
+                write!(f, "There isn't a match in this channel, join a team with `!team <name>` to start one"),
+            Error::MatchStarted =>
+                write!(f, "The match has already started, teams can't change now"),
+            Error::MatchInProgress =>
+                write!(f, "There's a match on in here, the team on turn picks the next game with `!pick`"),
+            Error::TeamsNotReady =>
+                write!(f, "A match needs two teams with at least one person in each"),
+            Error::NotInTeam =>
+                write!(f, "You're not in either team"),
+            Error::NotYourTurn(team) =>
+                write!(f, "It's {}'s turn", team),
+            Error::NotAllowed(role) =>
+                write!(f, "You need the {} role to do that", role),
+            Error::NotYourGame(id) =>
+                write!(f, "Game `{}` was submitted by someone else", id),
+            Error::Unclaimed(id) =>
+                write!(f, "Game `{}` is from before Nancy kept track of who submitted what, ask whoever runs Nancy to `!claim` it for you", id),
+            Error::AlreadyClaimed(id) =>
+                write!(f, "Game `{}` already belongs to someone", id),
+            Error::OwnersOnly =>
+                write!(f, "Only whoever runs Nancy can do that"),
+            Error::TimedOut =>
+                write!(f, "Nobody answered for a while so I gave up, nothing was added"),
         }
     }
 }
@@ -52,3 +119,15 @@ impl From<serde_json::Error> for Error {
         Error::Serde(e.to_string())
     }
 }
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Error {
+        Error::Io(e.to_string())
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Error {
+        Error::Database(e.to_string())
+    }
+}
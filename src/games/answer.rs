@@ -0,0 +1,272 @@
+use std::fmt::{Formatter, Display, self};
+use regex::RegexBuilder;
+use serde::{Serialize, Deserialize};
+use unicode_normalization::{
+    UnicodeNormalization,
+    char::is_combining_mark,
+};
+
+const ARTICLES: [&str; 3] = ["a", "an", "the"];
+
+const UNITS: [&str; 20] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+    "eleven", "twelve", "thirteen", "fourteen", "fifteen", "sixteen", "seventeen", "eighteen",
+    "nineteen",
+];
+
+const TENS: [&str; 8] = ["twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety"];
+
+/// Compares guesses with answers, ignoring case, punctuation, accents, articles and whether
+/// numbers are written as words or digits, and letting through anything within `tolerance`
+/// edits of the answer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matcher {
+    pub tolerance: usize,
+}
+
+impl Matcher {
+    pub fn new(tolerance: usize) -> Matcher {
+        Matcher { tolerance }
+    }
+
+    /// Short answers get less slack than `tolerance` - at most one edit for every four
+    /// characters - otherwise "cat" would be right when the answer is "car"
+    pub fn matches(&self, guess: &str, answer: &str) -> bool {
+        let guess = normalise(guess);
+        let answer = normalise(answer);
+        if guess.is_empty() || answer.is_empty() {
+            return false;
+        }
+        let allowed = self.tolerance.min(answer.chars().count() / 4);
+        levenshtein(&guess, &answer) <= allowed
+    }
+}
+
+/// Another way of writing an answer. Plain text is matched just as forgivingly as the answer
+/// itself, regexes are for anything fiddlier and have to match the whole guess (ignoring case).
+///
+/// In json these are either a string or `{"regex": "..."}`, when submitting a game over DM
+/// a regex is written between slashes, eg. `/(teenage )?mutant ninja turtles/`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Accepted {
+    Text(String),
+    Regex { regex: String },
+}
+
+impl Accepted {
+    pub fn parse(s: &str) -> Accepted {
+        let s = s.trim();
+        if s.len() > 2 && s.starts_with('/') && s.ends_with('/') {
+            Accepted::Regex { regex: s[1..s.len() - 1].to_string() }
+        } else {
+            Accepted::Text(s.to_string())
+        }
+    }
+
+    pub fn matches(&self, guess: &str, matcher: &Matcher) -> bool {
+        match self {
+            Accepted::Text(answer) => matcher.matches(guess, answer),
+            Accepted::Regex { regex } => RegexBuilder::new(&format!("^(?:{})$", regex))
+                .case_insensitive(true)
+                .build()
+                .map(|re| re.is_match(guess.trim()) || re.is_match(&normalise(guess)))
+                .unwrap_or_else(|e| {
+                    log::warn!("Bad answer regex {}: {}", regex, e);
+                    false
+                }),
+        }
+    }
+}
+
+impl Display for Accepted {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Accepted::Text(s) => write!(f, "{}", s),
+            Accepted::Regex { regex } => write!(f, "/{}/", regex),
+        }
+    }
+}
+
+/// Formats accepted answers as a spoilered list for reveals, eg. ` (also accepted: ||TMNT||)`
+pub fn also_accepted(accepted: &[Accepted]) -> String {
+    if accepted.is_empty() {
+        return String::new();
+    }
+    let list = accepted.iter()
+        .map(|a| format!("||{}||", a))
+        .collect::<Vec<String>>()
+        .join(", ");
+    format!(" (also accepted: {})", list)
+}
+
+/// Lowercases, strips accents and punctuation, drops articles and turns number words into
+/// digits, eg. `"The Twenty-One Pilots!"` becomes `"21 pilots"`
+pub fn normalise(s: &str) -> String {
+    let cleaned = strip_accents(s)
+        .chars()
+        .filter(|c| *c != '\'' && *c != '’')
+        .collect::<String>()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>();
+    let words = cleaned
+        .split_whitespace()
+        .filter(|w| !ARTICLES.contains(w))
+        .collect::<Vec<&str>>();
+    numerals(&words).join(" ")
+}
+
+/// Takes accents off letters and turns compatibility characters like full width letters into
+/// the plain ones, eg. `"Beyoncé"` becomes `"Beyonce"`
+pub fn strip_accents(s: &str) -> String {
+    s.nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .collect()
+}
+
+/// Replaces number words (up to ninety nine) with digits
+fn numerals(words: &[&str]) -> Vec<String> {
+    let unit = |w: &str| UNITS.iter().position(|u| *u == w);
+    let tens = |w: &str| TENS.iter().position(|t| *t == w).map(|t| (t + 2) * 10);
+    let mut out = vec![];
+    let mut i = 0;
+    while i < words.len() {
+        match (tens(words[i]), words.get(i + 1).and_then(|w| unit(w))) {
+            (Some(t), Some(u)) if u > 0 && u < 10 => {
+                out.push((t + u).to_string());
+                i += 2;
+            },
+            (Some(t), _) => {
+                out.push(t.to_string());
+                i += 1;
+            },
+            (None, _) => {
+                out.push(unit(words[i])
+                    .map(|u| u.to_string())
+                    .unwrap_or_else(|| words[i].to_string()));
+                i += 1;
+            },
+        }
+    }
+    out
+}
+
+/// The number of single character insertions, deletions or substitutions to get from `a`
+/// to `b`
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<char>>();
+    let mut row = (0..=b.len()).collect::<Vec<usize>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            let next = (row[j + 1] + 1)
+                .min(row[j] + 1)
+                .min(prev + cost);
+            prev = row[j + 1];
+            row[j + 1] = next;
+        }
+    }
+    row[b.len()]
+}
+
+/// The text inside the first `||spoiler||` in a message, if there is one
+pub fn spoiler(content: &str) -> Option<&str> {
+    let start = content.find("||")? + 2;
+    let len = content[start..].find("||")?;
+    Some(content[start..start + len].trim())
+        .filter(|s| !s.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalise_ignores_case_punctuation_and_accents() {
+        assert_eq!(normalise("The Twenty-One Pilots!"), "21 pilots");
+        assert_eq!(normalise("Café"), "cafe");
+        assert_eq!(normalise("Beyoncé's  Songs"), "beyonces songs");
+        assert_eq!(normalise("Ｆｕｌｌ Ｗｉｄｔｈ"), "full width");
+    }
+
+    #[test]
+    fn normalise_drops_articles_but_not_letters() {
+        assert_eq!(normalise("A Tale of the Two Cities"), "tale of 2 cities");
+        assert_eq!(normalise("an apple"), "apple");
+        assert_eq!(normalise("theatre"), "theatre");
+    }
+
+    #[test]
+    fn numerals_turns_words_into_digits() {
+        let words = |s: &str| numerals(&s.split_whitespace().collect::<Vec<&str>>()).join(" ");
+        assert_eq!(words("seventeen"), "17");
+        assert_eq!(words("twenty"), "20");
+        assert_eq!(words("twenty one"), "21");
+        assert_eq!(words("ninety nine"), "99");
+        assert_eq!(words("twenty zero"), "20 0");
+        assert_eq!(words("twenty eleven"), "20 11");
+        assert_eq!(words("seven dwarves"), "7 dwarves");
+    }
+
+    #[test]
+    fn levenshtein_counts_edits() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("abc", ""), 3);
+        assert_eq!(levenshtein("same", "same"), 0);
+    }
+
+    #[test]
+    fn short_answers_get_less_slack() {
+        let matcher = Matcher::new(2);
+        assert!(!matcher.matches("cat", "car"));
+        assert!(matcher.matches("car", "car"));
+        assert!(matcher.matches("pythn", "python"));
+        assert!(!matcher.matches("pyhn", "python"));
+    }
+
+    #[test]
+    fn long_answers_get_the_full_tolerance() {
+        let matcher = Matcher::new(2);
+        assert!(matcher.matches("mutnt ninja turtls", "mutant ninja turtles"));
+        assert!(!matcher.matches("mtnt nnja turtles", "mutant ninja turtles"));
+        assert!(!Matcher::new(0).matches("mutnt ninja turtles", "mutant ninja turtles"));
+    }
+
+    #[test]
+    fn matches_ignores_articles_and_number_words() {
+        let matcher = Matcher::new(0);
+        assert!(matcher.matches("The Beatles", "beatles"));
+        assert!(matcher.matches("twenty one pilots", "21 Pilots"));
+    }
+
+    #[test]
+    fn empty_guesses_never_match() {
+        let matcher = Matcher::new(2);
+        assert!(!matcher.matches("", "answer"));
+        assert!(!matcher.matches("!!!", "answer"));
+        assert!(!matcher.matches("the", "the"));
+    }
+
+    #[test]
+    fn accepted_regexes_match_the_whole_guess() {
+        let matcher = Matcher::new(0);
+        let accepted = Accepted::parse("/(the )?(four )?elements/");
+        assert_eq!(accepted, Accepted::Regex { regex: "(the )?(four )?elements".to_string() });
+        assert!(accepted.matches("The Four Elements", &matcher));
+        assert!(accepted.matches("elements", &matcher));
+        assert!(!accepted.matches("five elements", &matcher));
+        assert_eq!(Accepted::parse("//"), Accepted::Text("//".to_string()));
+    }
+
+    #[test]
+    fn spoiler_finds_the_first_tag() {
+        assert_eq!(spoiler("my guess ||  paris || ok"), Some("paris"));
+        assert_eq!(spoiler("|| ||"), None);
+        assert_eq!(spoiler("no spoiler here"), None);
+    }
+}
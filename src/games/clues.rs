@@ -0,0 +1,108 @@
+use serde::Deserialize;
+use crate::{
+    games::game::GameState,
+    error::Error,
+};
+
+/// Clues as they're written in json, either a `clues` list or `clue1`, `clue2`... the way games
+/// were saved before they could have different numbers of clues
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all="kebab-case")]
+pub struct LegacyClues {
+    #[serde(default)]
+    clues: Vec<String>,
+    clue1: Option<String>,
+    clue2: Option<String>,
+    clue3: Option<String>,
+    clue4: Option<String>,
+    clue5: Option<String>,
+    clue6: Option<String>,
+    clue7: Option<String>,
+}
+
+impl LegacyClues {
+    pub fn into_vec(self) -> Vec<String> {
+        if !self.clues.is_empty() {
+            return self.clues;
+        }
+        vec![self.clue1, self.clue2, self.clue3, self.clue4, self.clue5, self.clue6, self.clue7]
+            .into_iter()
+            .take_while(Option::is_some)
+            .flatten()
+            .collect()
+    }
+}
+
+/// Checks there are between `min` and `max` clues and none of them are blank
+pub fn validate(clues: &[String], min: usize, max: usize) -> Result<(), Error> {
+    if clues.len() < min || clues.len() > max {
+        return Err(Error::ArgError(format!("Needs between {} and {} clues but there are {}", min, max, clues.len())));
+    }
+    match clues.iter().position(|c| c.trim().is_empty()) {
+        Some(i) => Err(Error::ArgError(format!("Clue {} is blank", i + 1))),
+        None => Ok(()),
+    }
+}
+
+/// The first `shown` clues as a numbered list, one per line
+pub fn numbered(clues: &[String], shown: usize) -> String {
+    clues.iter()
+        .take(shown)
+        .enumerate()
+        .map(|(i, c)| format!("{}.\t{}\n", i + 1, c.replace("\n", "\n\t\t")))
+        .collect()
+}
+
+/// Shows one more clue than `state` has, up to `count` of them (fewer than there are for a
+/// sequence, where the last one is the answer)
+pub fn next_clue(clues: &[String], count: usize, state: &GameState) -> (Option<String>, GameState) {
+    let shown = match state {
+        GameState::Ready => 1,
+        GameState::Clue(i) => *i as usize + 1,
+        _ => return (None, state.clone()),
+    };
+    if shown > count {
+        return (None, GameState::NoCluesLeft);
+    }
+    let state = if shown == count {
+        GameState::NoCluesLeft
+    } else {
+        GameState::Clue(shown as i64)
+    };
+    (Some(numbered(clues, shown)), state)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::games::{
+        game::{Game, GameType},
+        link::LinkGame,
+    };
+
+    #[test]
+    fn old_numbered_clues_still_load() {
+        let game: Game = serde_json::from_str(include_str!("../../games/ninja-turtles.json")).unwrap();
+        assert_eq!(game.submitted_by, "mattswoon");
+        assert_eq!(game.submitter_id, None);
+        match game.game {
+            GameType::Link(LinkGame::Text(link)) => {
+                assert_eq!(link.clues, vec!["Michaelangelo", "Donatello", "Raphael", "Leonardo"]);
+                assert_eq!(link.answer, "The Teenage Mutant Ninja Turtles");
+                assert!(link.accepted.is_empty());
+            },
+            other => panic!("expected a link game, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn a_clue_list_wins_over_numbered_clues() {
+        let clues: super::LegacyClues = serde_json::from_str(r#"{"clues": ["a", "b", "c"], "clue1": "x"}"#).unwrap();
+        assert_eq!(clues.into_vec(), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn numbered_clues_stop_at_the_first_gap() {
+        let clues: super::LegacyClues = serde_json::from_str(r#"{"clue1": "a", "clue2": "b", "clue4": "d"}"#).unwrap();
+        assert_eq!(clues.into_vec(), vec!["a", "b"]);
+    }
+}
@@ -1,20 +1,142 @@
 use std::fmt::{Formatter, Display, self};
 use serde::{Serialize, Deserialize};
-use crate::games::{
-    sequence::{
-        SequenceGame,
-    },
-    link::{
-        LinkGame,
+use serenity::model::{
+    id::UserId,
+    user::User,
+};
+use crate::{
+    games::{
+        sequence::{
+            SequenceGame,
+        },
+        link::{
+            LinkGame,
+        },
+        wall::{
+            WallGame,
+            WallProgress,
+        },
+        vowels::{
+            MissingVowelsGame,
+            VowelsProgress,
+        },
+        traits::{Playable, Guess, Rules},
     },
+    error::Error,
 };
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all="kebab-case")]
 pub struct Game {
+    #[serde(default, skip_serializing_if="String::is_empty")]
+    pub id: String,
     pub submitted_by: String,
+    /// Who submitted it, games from before this was kept only have their name
+    #[serde(default, skip_serializing_if="Option::is_none")]
+    pub submitter_id: Option<UserId>,
     #[serde(flatten)]
     pub game: GameType,
+    /// How many rounds this game has been queued for
+    #[serde(default)]
+    pub plays: u32,
+    /// Unix timestamp (seconds) of the last time it was queued
+    #[serde(default)]
+    pub last_played: Option<u64>,
+    /// Where it's got to in the moderation queue, games from before there was one are approved
+    #[serde(default)]
+    pub review: Review,
+}
+
+/// Whether a moderator has looked at a game. Guilds that turn moderation on only play approved
+/// games, everywhere else plays pending ones too. Rejected games aren't played anywhere.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all="kebab-case")]
+pub enum Review {
+    Pending,
+    Approved,
+    Rejected(String),
+}
+
+impl Default for Review {
+    fn default() -> Review {
+        Review::Approved
+    }
+}
+
+impl Review {
+    /// Whether the game can be played in a guild, `moderated` if the guild has moderation on
+    pub fn is_playable(&self, moderated: bool) -> bool {
+        match self {
+            Review::Approved => true,
+            Review::Pending => !moderated,
+            Review::Rejected(_) => false,
+        }
+    }
+
+    /// What it's called in the `review` column of the sqlite store
+    pub fn name(&self) -> &'static str {
+        match self {
+            Review::Pending => "pending",
+            Review::Approved => "approved",
+            Review::Rejected(_) => "rejected",
+        }
+    }
+}
+
+impl Game {
+    /// A game that hasn't been saved yet, it gets an id when it's added to the games directory
+    pub fn new(submitted_by: &User, game: GameType) -> Game {
+        Game {
+            id: String::new(),
+            submitted_by: submitted_by.name.clone(),
+            submitter_id: Some(submitted_by.id),
+            game,
+            plays: 0,
+            last_played: None,
+            review: Review::Pending,
+        }
+    }
+
+    /// Who to credit for the game, a proper mention if `mention` is set and we know who they
+    /// are, otherwise the name they had when they submitted it
+    pub fn submitter(&self, mention: bool) -> String {
+        match self.submitter_id {
+            Some(id) if mention => format!("<@{}>", id),
+            _ => self.submitted_by.clone(),
+        }
+    }
+
+    /// The whole game with who submitted it, see `submitter` for `mention`
+    pub fn describe(&self, mention: bool) -> String {
+        format!("Submitted by: {}\n\n{}", self.submitter(mention), self.game)
+    }
+
+    /// Checks the game makes sense. Anything that doesn't is never picked to play, since rounds
+    /// of it could go wrong half way through.
+    pub fn validate(&self) -> Result<(), Error> {
+        self.game.playable().validate()
+    }
+
+    /// Whether the game can be played, logging why not if it can't
+    pub fn is_valid(&self) -> bool {
+        match self.validate() {
+            Ok(()) => true,
+            Err(e) => {
+                log::warn!("Game {} can't be played until it's fixed: {}", self.id, e);
+                false
+            },
+        }
+    }
+
+    pub fn is_unplayed(&self) -> bool {
+        self.plays == 0
+    }
+
+    /// Whether `user` can edit or delete this game. Older games without a submitter id don't
+    /// belong to anyone until they're claimed, names aren't unique and people change them.
+    pub fn is_submitted_by(&self, user: &User) -> bool {
+        self.submitter_id == Some(user.id)
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -22,18 +144,46 @@ pub struct Game {
 pub enum GameType {
     Sequence(SequenceGame),
     Link(LinkGame),
+    Wall(WallGame),
+    MissingVowels(MissingVowelsGame),
+}
+
+impl GameType {
+    /// What it's called in `!status` and `!my-games`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GameType::Link(_) => "link",
+            GameType::Sequence(_) => "sequence",
+            GameType::Wall(_) => "wall",
+            GameType::MissingVowels(_) => "missing vowels",
+        }
+    }
+
+    /// The game itself, which knows how its rounds go
+    pub fn playable(&self) -> &dyn Playable {
+        match self {
+            GameType::Link(LinkGame::Text(g)) => g,
+            GameType::Sequence(SequenceGame::Text(g)) => g,
+            GameType::Wall(WallGame::Text(g)) => g,
+            GameType::MissingVowels(MissingVowelsGame::Text(g)) => g,
+        }
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all="kebab-case")]
 pub enum GameState {
     Ready,
     Clue(i64),
     NoCluesLeft,
+    Wall(WallProgress),
+    Vowels(VowelsProgress),
     Answered,
 }
 
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all="kebab-case")]
 pub struct PlayingGame {
     pub game: Game,
     pub state: GameState,
@@ -47,6 +197,27 @@ impl PlayingGame {
         }
     }
 
+    /// Whether there's a clue out there for people to guess at, for walls that's once it's
+    /// down to naming the connections
+    pub fn is_guessable(&self) -> bool {
+        self.game.game.playable().is_guessable(&self.state)
+    }
+
+    pub fn clues_shown(&self) -> usize {
+        self.game.game.playable().clues_shown(&self.state)
+    }
+
+    /// Whether there's nothing left to do but reveal the answers
+    pub fn is_over(&self) -> bool {
+        self.game.game.playable().is_over(&self.state)
+    }
+
+    pub fn guess(&self, guess: &str, rules: &Rules) -> Guess {
+        self.game.game
+            .playable()
+            .guess(&self.state, guess, rules)
+    }
+
     pub fn with_state(self, state: GameState) -> Self {
         PlayingGame {
             state,
@@ -54,61 +225,26 @@ impl PlayingGame {
         }
     }
 
-    pub fn next_clue(self) -> (Option<String>, GameState) {
-        match &self.state {
-            GameState::Ready => {
-                match self.game.game {
-                    GameType::Link(LinkGame::Text(g)) => (Some(format!("1.\t{}\n", g.clue1.replace("\n", "\n\t\t"))), GameState::Clue(1)),
-                    GameType::Sequence(SequenceGame::Text(g)) => (Some(format!("1.\t{}\n", g.clue1.replace("\n", "\n\t\t"))), GameState::Clue(1)),
-                }
-            },
-            GameState::Clue(i) => {
-                match self.game.game {
-                    GameType::Link(LinkGame::Text(g)) => {
-                        match &i {
-                            1 => (Some(format!("1.\t{}\n2.\t{}\n", g.clue1.replace("\n", "\n\t\t"), g.clue2.replace("\n", "\n\t\t"))), GameState::Clue(2)),
-                            2 => (Some(format!("1.\t{}\n2.\t{}\n3.\t{}\n", g.clue1.replace("\n", "\n\t\t"), g.clue2.replace("\n", "\n\t\t"), g.clue3.replace("\n", "\n\t\t"))), GameState::Clue(3)),
-                            3 => (Some(format!("1.\t{}\n2.\t{}\n3.\t{}\n4.\t{}\n", g.clue1.replace("\n", "\n\t\t"), g.clue2.replace("\n", "\n\t\t"), g.clue3.replace("\n", "\n\t\t"), g.clue4.replace("\n", "\n\t\t"))), GameState::NoCluesLeft),
-                            _ => (None, GameState::NoCluesLeft),
-                        }
-                    },
-                    GameType::Sequence(SequenceGame::Text(g)) => {
-                        match &i {
-                            1 => (Some(format!("1.\t{}\n2.\t{}\n", g.clue1.replace("\n", "\n\t\t"), g.clue2.replace("\n", "\n\t\t"))), GameState::Clue(2)),
-                            2 => (Some(format!("1.\t{}\n2.\t{}\n3.\t{}\n", g.clue1.replace("\n", "\n\t\t"), g.clue2.replace("\n", "\n\t\t"), g.clue3.replace("\n", "\n\t\t"))), GameState::Clue(3)),
-                            3 => (Some(format!("1.\t{}\n2.\t{}\n3.\t{}\n4.\t{}\n", g.clue1.replace("\n", "\n\t\t"), g.clue2.replace("\n", "\n\t\t"), g.clue3.replace("\n", "\n\t\t"), g.clue4.replace("\n", "\n\t\t"))), GameState::NoCluesLeft),
-                            4 => (Some(format!("1.\t{}\n2.\t{}\n3.\t{}\n4.\t{}\n5.\t{}\n", g.clue1.replace("\n", "\n\t\t"), g.clue2.replace("\n", "\n\t\t"), g.clue3.replace("\n", "\n\t\t"), g.clue4.replace("\n", "\n\t\t"), g.clue5.replace("\n", "\n\t\t"))), GameState::NoCluesLeft),
-                            _ => (None, GameState::NoCluesLeft),
-                        }
-                    },
-                }
-            },
-            _ => (None, self.state)
-        }
+    pub fn next_clue(self, rules: &Rules) -> (Option<String>, GameState) {
+        self.game.game
+            .playable()
+            .next_clue(&self.state, rules)
     }
 
-    pub fn reveal(self) -> (String, GameState) {
-        let submitted_by = self.game.submitted_by.clone();
-        match self.game.game {
-            GameType::Link(LinkGame::Text(g)) => (format!("Submitted by: {}\n\n{}", submitted_by, g), GameState::Answered),
-            GameType::Sequence(SequenceGame::Text(g)) => (format!("Submitted by: {}\n\n{}", submitted_by, g), GameState::Answered),
-        }
+    /// The answers, crediting the submitter with a mention if `mention` is set
+    pub fn reveal(self, mention: bool) -> (String, GameState) {
+        (self.game.describe(mention), GameState::Answered)
     }
 }
 
 impl Display for GameType {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        match self {
-            GameType::Link(LinkGame::Text(g)) => 
-                write!(f, "{}", g),
-            GameType::Sequence(SequenceGame::Text(g)) =>
-                write!(f, "{}", g),
-        }
+        write!(f, "{}", self.playable().reveal_answer())
     }
 }
 
 impl Display for Game {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "Submitted by: {}\n\n{}", self.submitted_by, self.game)
+        write!(f, "{}", self.describe(false))
     }
 }
@@ -1,5 +1,19 @@
 use std::fmt::{Formatter, Display, self};
 use serde::{Serialize, Deserialize};
+use crate::games::answer::{
+    Accepted,
+    Matcher,
+    also_accepted,
+};
+use crate::games::media::{self, ClueImage};
+use crate::games::clues::{self, LegacyClues};
+use crate::games::game::GameState;
+use crate::games::traits::{Playable, NextClue, RevealAnswer, Guess, Rules};
+use crate::error::Error;
+
+/// A sprint has three clues, the classic game four, and a marathon six
+pub const MIN_CLUES: usize = 3;
+pub const MAX_CLUES: usize = 6;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(tag="link-game-type", rename_all="kebab-case")]
@@ -8,30 +22,102 @@ pub enum LinkGame {
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-#[serde(rename_all="kebab-case")]
+#[serde(rename_all="kebab-case", from="RawTextLink")]
 pub struct TextLink {
-    pub clue1: String,
-    pub clue2: String,
-    pub clue3: String,
-    pub clue4: String,
+    pub clues: Vec<String>,
     pub answer: String,
+    /// Other ways of writing the answer that count as right
+    #[serde(skip_serializing_if="Vec::is_empty")]
+    pub accepted: Vec<Accepted>,
+    /// Pictures posted along with the clues, the clue text is their alt text
+    #[serde(skip_serializing_if="Vec::is_empty")]
+    pub images: Vec<ClueImage>,
+}
+
+/// What `TextLink` is read from, so games saved with `clue1`..`clue4` still load
+#[derive(Deserialize)]
+#[serde(rename_all="kebab-case")]
+struct RawTextLink {
+    #[serde(flatten)]
+    clues: LegacyClues,
+    answer: String,
+    #[serde(default)]
+    accepted: Vec<Accepted>,
+    #[serde(default)]
+    images: Vec<ClueImage>,
+}
+
+impl From<RawTextLink> for TextLink {
+    fn from(raw: RawTextLink) -> TextLink {
+        TextLink {
+            clues: raw.clues.into_vec(),
+            answer: raw.answer,
+            accepted: raw.accepted,
+            images: raw.images,
+        }
+    }
+}
+
+impl TextLink {
+    pub fn check_answer(&self, guess: &str, matcher: &Matcher) -> bool {
+        matcher.matches(guess, &self.answer)
+            || self.accepted.iter().any(|a| a.matches(guess, matcher))
+    }
+}
+
+impl Playable for TextLink {
+    fn prompt(&self) -> &'static str {
+        "Guess what connects the clues"
+    }
+
+    fn clue_count(&self) -> usize {
+        self.clues.len()
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        clues::validate(&self.clues, MIN_CLUES, MAX_CLUES)?;
+        media::validate(&self.images)
+    }
+
+    fn guess(&self, _state: &GameState, guess: &str, rules: &Rules) -> Guess {
+        if self.check_answer(guess, &rules.matcher) {
+            Guess::Solved
+        } else {
+            Guess::Wrong
+        }
+    }
+
+    fn summary(&self) -> String {
+        format!("{} → {}", self.clues.join(", "), self.answer)
+    }
+
+    fn images(&self) -> &[ClueImage] {
+        &self.images
+    }
+
+    fn clues(&self) -> &[String] {
+        &self.clues
+    }
+}
+
+impl NextClue for TextLink {
+    fn next_clue(&self, state: &GameState, _rules: &Rules) -> (Option<String>, GameState) {
+        clues::next_clue(&self.clues, self.clue_count(), state)
+    }
+}
+
+impl RevealAnswer for TextLink {
+    fn reveal_answer(&self) -> String {
+        self.to_string()
+    }
 }
 
 impl Display for TextLink {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f,
-r#"Solve for the link
-
-    1. {}
-    2. {}
-    3. {}
-    4. {}
-
-Answer: ||{}||
-"#, self.clue1.replace("\n", "\n\t\t"), 
-self.clue2.replace("\n", "\n\t\t"), 
-self.clue3.replace("\n", "\n\t\t"), 
-self.clue4.replace("\n", "\n\t\t"), 
-self.answer.replace("\n", "\n\t\t"))
+        writeln!(f, "Solve for the link\n")?;
+        for (i, c) in self.clues.iter().enumerate() {
+            writeln!(f, "    {}. {}", i + 1, c.replace("\n", "\n\t\t"))?;
+        }
+        writeln!(f, "\nAnswer: ||{}||{}", self.answer.replace("\n", "\n\t\t"), also_accepted(&self.accepted))
     }
 }
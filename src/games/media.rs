@@ -0,0 +1,82 @@
+use std::path::{Component, Path};
+use serde::{Serialize, Deserialize};
+use crate::error::Error;
+
+/// What a clue line starts with when submitting over DM if it's one of the attached images
+pub const IMAGE_MARKER: &str = "[image]";
+
+/// What's shown in place of an image clue that doesn't have any alt text
+pub const NO_ALT_TEXT: &str = "(picture)";
+
+/// A picture standing in for (or alongside) one of the clues, kept in the images directory so
+/// it doesn't matter if the original upload disappears
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all="kebab-case")]
+pub struct ClueImage {
+    /// Which clue it's for, counting from 1
+    pub clue: usize,
+    /// The file name in the images directory
+    pub file: String,
+    #[serde(default, skip_serializing_if="Option::is_none")]
+    pub alt: Option<String>,
+}
+
+/// A picture that's been downloaded for a clue but isn't saved yet, it's only saved once the
+/// game it's for has been checked over
+#[derive(Clone, Debug)]
+pub struct ImageUpload {
+    pub clue: usize,
+    pub alt: Option<String>,
+    pub extension: String,
+    pub contents: Vec<u8>,
+}
+
+/// If a clue line is marked as an image, gives back its alt text (if it has any), eg.
+/// `"[image] a red planet"` gives `Some(Some("a red planet"))`
+pub fn image_marker(line: &str) -> Option<Option<String>> {
+    let line = line.trim();
+    if !line.to_lowercase().starts_with(IMAGE_MARKER) {
+        return None;
+    }
+    let alt = line.get(IMAGE_MARKER.len()..).unwrap_or("").trim();
+    Some(Some(alt.to_string()).filter(|a| !a.is_empty()))
+}
+
+/// The images for one clue
+pub fn for_clue(images: &[ClueImage], clue: usize) -> impl Iterator<Item=&ClueImage> {
+    images.iter().filter(move |i| i.clue == clue)
+}
+
+/// Checks every image is a bare file name, so a game can't point at files outside the images
+/// directory
+pub fn validate(images: &[ClueImage]) -> Result<(), Error> {
+    match images.iter().find(|i| !is_bare_file_name(&i.file)) {
+        Some(i) => Err(Error::ArgError(format!("`{}` isn't the name of a picture in the images directory", i.file))),
+        None => Ok(()),
+    }
+}
+
+/// A file name with no directories in it, eg. `"17b2c9a41e0-3f2a.png"`
+fn is_bare_file_name(file: &str) -> bool {
+    let mut components = Path::new(file).components();
+    !file.contains('/')
+        && !file.contains('\\')
+        && matches!((components.next(), components.next()), (Some(Component::Normal(_)), None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(file: &str) -> ClueImage {
+        ClueImage { clue: 1, file: file.to_string(), alt: None }
+    }
+
+    #[test]
+    fn only_bare_file_names_are_valid() {
+        assert!(validate(&[image("17b2c9a41e0-3f2a.png")]).is_ok());
+        for file in &["", ".", "..", "../../etc/passwd", "/etc/passwd", "images/a.png", "..\\a.png", "C:\\a.png"] {
+            assert!(validate(&[image(file)]).is_err(), "{} should be rejected", file);
+        }
+    }
+}
@@ -1,4 +1,9 @@
 pub mod link;
 pub mod sequence;
+pub mod wall;
+pub mod vowels;
+pub mod media;
+pub mod clues;
 pub mod game;
-//pub mod traits;
+pub mod answer;
+pub mod traits;
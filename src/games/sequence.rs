@@ -1,5 +1,20 @@
 use serde::{Serialize, Deserialize};
 use std::fmt::{Formatter, Display, self};
+use crate::games::answer::{
+    Accepted,
+    Matcher,
+    also_accepted,
+};
+use crate::games::media::{self, ClueImage};
+use crate::games::clues::{self, LegacyClues};
+use crate::games::game::GameState;
+use crate::games::traits::{Playable, NextClue, RevealAnswer, Guess, Rules};
+use crate::error::Error;
+
+/// Counting the element the players have to guess, so a sprint shows two clues before it and a
+/// marathon six
+pub const MIN_CLUES: usize = 3;
+pub const MAX_CLUES: usize = 7;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(tag="sequence-game-type", rename_all="kebab-case")]
@@ -8,33 +23,174 @@ pub enum SequenceGame {
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-#[serde(rename_all="kebab-case")]
+#[serde(rename_all="kebab-case", from="RawTextSequence")]
 pub struct TextSequence {
-    pub clue1: String,
-    pub clue2: String,
-    pub clue3: String,
-    pub clue4: String,
-    pub clue5: String,
+    /// The elements of the sequence in order, the last one is what the players are after
+    pub clues: Vec<String>,
+    /// How the sequence works, shown when the answer is revealed
     pub answer: String,
+    /// Other ways of writing the last element that count as right
+    #[serde(skip_serializing_if="Vec::is_empty")]
+    pub accepted: Vec<Accepted>,
+    /// A short name for the pattern, naming it counts as right too. `answer` is the longer
+    /// explanation, which nobody would type as a guess.
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub pattern: Option<String>,
+    /// Pictures posted along with the clues, the clue text is their alt text
+    #[serde(skip_serializing_if="Vec::is_empty")]
+    pub images: Vec<ClueImage>,
+}
+
+/// What `TextSequence` is read from, so games saved with `clue1`..`clue5` still load
+#[derive(Deserialize)]
+#[serde(rename_all="kebab-case")]
+struct RawTextSequence {
+    #[serde(flatten)]
+    clues: LegacyClues,
+    answer: String,
+    #[serde(default)]
+    accepted: Vec<Accepted>,
+    #[serde(default)]
+    pattern: Option<String>,
+    #[serde(default)]
+    images: Vec<ClueImage>,
+}
+
+impl From<RawTextSequence> for TextSequence {
+    fn from(raw: RawTextSequence) -> TextSequence {
+        TextSequence {
+            clues: raw.clues.into_vec(),
+            answer: raw.answer,
+            accepted: raw.accepted,
+            pattern: raw.pattern,
+            images: raw.images,
+        }
+    }
+}
+
+impl TextSequence {
+    /// The element the players are trying to guess
+    pub fn last(&self) -> &str {
+        self.clues.last().map(String::as_str).unwrap_or_default()
+    }
+
+    /// Right if it's the last element or the pattern
+    pub fn check_answer(&self, guess: &str, matcher: &Matcher) -> bool {
+        matcher.matches(guess, self.last())
+            || self.accepted.iter().any(|a| a.matches(guess, matcher))
+            || self.pattern.iter().any(|p| matcher.matches(guess, p))
+    }
+}
+
+impl Playable for TextSequence {
+    fn prompt(&self) -> &'static str {
+        "Guess the last element in the sequence (and name the pattern)"
+    }
+
+    /// The last element is the answer so it's not counted
+    fn clue_count(&self) -> usize {
+        self.clues.len().saturating_sub(1)
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        clues::validate(&self.clues, MIN_CLUES, MAX_CLUES)?;
+        media::validate(&self.images)
+    }
+
+    fn guess(&self, _state: &GameState, guess: &str, rules: &Rules) -> Guess {
+        if self.check_answer(guess, &rules.matcher) {
+            Guess::Solved
+        } else {
+            Guess::Wrong
+        }
+    }
+
+    fn summary(&self) -> String {
+        format!("{} ({})", self.clues.join(", "), self.pattern.as_ref().unwrap_or(&self.answer))
+    }
+
+    fn images(&self) -> &[ClueImage] {
+        &self.images
+    }
+
+    fn clues(&self) -> &[String] {
+        &self.clues
+    }
+}
+
+impl NextClue for TextSequence {
+    fn next_clue(&self, state: &GameState, _rules: &Rules) -> (Option<String>, GameState) {
+        clues::next_clue(&self.clues, self.clue_count(), state)
+    }
+}
+
+impl RevealAnswer for TextSequence {
+    fn reveal_answer(&self) -> String {
+        self.to_string()
+    }
 }
 
 impl Display for TextSequence {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f,
-r#"What's the fifth element of the sequence?
-
-    1.  {}
-    2.  {}
-    3.  {}
-    4.  {}
-    5.  ||{}||
-
-Answer: ||{}||
-"#, self.clue1.replace("\n", "\n\t\t"), 
-self.clue2.replace("\n", "\n\t\t"), 
-self.clue3.replace("\n", "\n\t\t"), 
-self.clue4.replace("\n", "\n\t\t"), 
-self.clue5.replace("\n", "\n\t\t"), 
-self.answer.replace("\n", "\n\t\t"))
+        writeln!(f, "What's the last element of the sequence?\n")?;
+        let hidden = self.clues.len().saturating_sub(1);
+        for (i, c) in self.clues.iter().enumerate() {
+            if i < hidden {
+                writeln!(f, "    {}.  {}", i + 1, c.replace("\n", "\n\t\t"))?;
+            } else {
+                writeln!(f, "    {}.  ||{}||{}", i + 1, c.replace("\n", "\n\t\t"), also_accepted(&self.accepted))?;
+            }
+        }
+        writeln!(f, "\n{}Answer: ||{}||",
+                 self.pattern.as_ref().map(|p| format!("Pattern: ||{}||\n", p)).unwrap_or_default(),
+                 self.answer.replace("\n", "\n\t\t"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vowels() -> TextSequence {
+        TextSequence {
+            clues: ["a", "e", "i", "o", "u"].iter().map(|c| c.to_string()).collect(),
+            answer: "The vowels in alphabetical order".to_string(),
+            accepted: vec![Accepted::Text("you".to_string())],
+            pattern: Some("vowels".to_string()),
+            images: vec![],
+        }
+    }
+
+    #[test]
+    fn the_last_element_or_the_pattern_is_right() {
+        let matcher = Matcher::new(2);
+        let game = vowels();
+        assert!(game.check_answer("U", &matcher));
+        assert!(game.check_answer("you", &matcher));
+        assert!(game.check_answer("Vowels", &matcher));
+        assert!(!game.check_answer("o", &matcher));
+    }
+
+    #[test]
+    fn the_explanation_isnt_an_answer() {
+        let matcher = Matcher::new(2);
+        assert!(!vowels().check_answer("the vowels in alphabetical order", &matcher));
+    }
+
+    #[test]
+    fn legacy_clues_still_load() {
+        let json = r#"{
+            "clue1": "a",
+            "clue2": "e",
+            "clue3": "i",
+            "clue4": "o",
+            "clue5": "u",
+            "answer": "vowels"
+        }"#;
+        let game: TextSequence = serde_json::from_str(json).unwrap();
+        assert_eq!(game.clues, vec!["a", "e", "i", "o", "u"]);
+        assert_eq!(game.last(), "u");
+        assert_eq!(game.pattern, None);
+        assert!(game.validate().is_ok());
     }
 }
@@ -1,25 +1,104 @@
-pub trait Playable: std::fmt::Debug + Send + Sync {
-    type State;
-    type ClueType;
-    type AnswerType;
+use crate::{
+    games::{
+        game::GameState,
+        answer::Matcher,
+        media::ClueImage,
+    },
+    error::Error,
+};
 
-    fn next(&self, state: Self::State) -> (Option<Self::ClueType>, Self::State);
+/// Everything `PlayingGame` needs from a game type to run a round of it
+pub trait Playable: NextClue + RevealAnswer + std::fmt::Debug + Send + Sync {
+    /// What the players are trying to do, shown with every clue
+    fn prompt(&self) -> &'static str;
 
-    fn reveal(&self) -> (Option<Self::AnswerType>, Self::State);
-}
+    /// What goes before the first clue when a round starts
+    fn intro(&self) -> &'static str {
+        "The first clue is"
+    }
 
-pub trait PlayState: std::fmt::Debug + Send + Sync {}
+    /// What goes before the clues after `!next-clue`, `None` to post just the clue
+    fn clues_heading(&self) -> Option<&'static str> {
+        Some("The clues so far are")
+    }
 
-pub trait NextClue {
-    type State;
-    type ClueType;
+    /// How many clues get shown before there are none left
+    fn clue_count(&self) -> usize;
+
+    /// How many clues the players have seen in `state`, which is what right answers are
+    /// scored on
+    fn clues_shown(&self, state: &GameState) -> usize {
+        match state {
+            GameState::Ready => 0,
+            GameState::Clue(i) => *i as usize,
+            _ => self.clue_count(),
+        }
+    }
+
+    /// Whether there's something out there for people to guess at in `state`
+    fn is_guessable(&self, state: &GameState) -> bool {
+        matches!(state, GameState::Clue(_) | GameState::NoCluesLeft)
+    }
+
+    /// Whether the players have got through everything in `state`, so all that's left is to
+    /// reveal the answers
+    fn is_over(&self, _state: &GameState) -> bool {
+        false
+    }
+
+    /// Checks the game makes sense before it's added
+    fn validate(&self) -> Result<(), Error>;
+
+    /// Judges a guess made in `state`
+    fn guess(&self, state: &GameState, guess: &str, rules: &Rules) -> Guess;
+
+    /// A line to tell the game apart from others, for listing someone's games
+    fn summary(&self) -> String;
 
-    fn next_clue(&self, state: Self::State) -> (Option<Self::ClueType>, Self::State);
+    /// Pictures that go with the clues
+    fn images(&self) -> &[ClueImage] {
+        &[]
+    }
+
+    /// The clues in the order they're shown, for games that are a list of them. Only the first
+    /// `clue_count` are clues, a sequence's last element is the answer.
+    fn clues(&self) -> &[String] {
+        &[]
+    }
+}
+
+pub trait NextClue {
+    /// The clue (or clues) to show after `state`, and the state after showing it. Anything that
+    /// isn't expecting another clue gives back `None` and the state it was given.
+    fn next_clue(&self, state: &GameState, rules: &Rules) -> (Option<String>, GameState);
 }
 
+/// Reveals everything, the clues and the answer (in spoiler tags)
 pub trait RevealAnswer {
-    type State;
-    type AnswerType;
+    fn reveal_answer(&self) -> String;
+}
+
+/// What a guess did to the round
+#[derive(Debug, Clone)]
+pub enum Guess {
+    Wrong,
+    /// Right, and that's the whole round solved. It's scored by how many clues were showing.
+    Solved,
+    /// Right, but only part of the round - the guesser gets `points` and the round goes on
+    /// from `state`, unless that's the end of it. `said` is what to post.
+    Progress {
+        state: GameState,
+        points: u32,
+        said: String,
+    },
+}
 
-    fn reveal_answer(&self, state: Self::State) -> (Option<Self::AnswerType>, Self::State);
+/// The guild's settings for the round being played
+#[derive(Debug, Clone)]
+pub struct Rules {
+    pub matcher: Matcher,
+    /// The guild's `Scoring::table` for the game, see `scores::points`
+    pub points: Vec<u32>,
+    /// How long players get to find the groups on a wall
+    pub wall_seconds: Option<u64>,
 }
@@ -0,0 +1,271 @@
+use std::fmt::{Formatter, Display, self};
+use rand::{
+    thread_rng,
+    Rng,
+};
+use serde::{Serialize, Deserialize};
+use crate::{
+    games::{
+        answer::{Matcher, strip_accents},
+        game::GameState,
+        traits::{Playable, NextClue, RevealAnswer, Guess, Rules},
+    },
+    error::Error,
+};
+
+const VOWELS: [char; 5] = ['A', 'E', 'I', 'O', 'U'];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag="missing-vowels-game-type", rename_all="kebab-case")]
+pub enum MissingVowelsGame {
+    Text(TextMissingVowels)
+}
+
+/// A category and the phrases in it, the puzzles are made from the phrases when the round starts
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all="kebab-case")]
+pub struct TextMissingVowels {
+    pub category: String,
+    pub phrases: Vec<String>,
+}
+
+impl Playable for TextMissingVowels {
+    fn prompt(&self) -> &'static str {
+        "Race to `!guess` each phrase - the vowels are gone and the spaces are in the wrong places"
+    }
+
+    fn intro(&self) -> &'static str {
+        "Here's the first one"
+    }
+
+    /// Skipping a phrase posts the next one on its own
+    fn clues_heading(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn clue_count(&self) -> usize {
+        self.phrases.len()
+    }
+
+    /// Checks there's something to play and that every phrase still has letters left once the
+    /// vowels are gone
+    fn validate(&self) -> Result<(), Error> {
+        if self.phrases.is_empty() {
+            return Err(Error::ArgError("A missing vowels game needs at least one phrase".to_string()));
+        }
+        match self.phrases.iter().find(|p| strip_vowels(p).is_empty()) {
+            Some(p) => Err(Error::ArgError(format!("There'd be nothing left of `{}` without its vowels", p))),
+            None => Ok(()),
+        }
+    }
+
+    /// Each phrase counts as a clue, so the one that's up is shown
+    fn clues_shown(&self, state: &GameState) -> usize {
+        match state {
+            GameState::Ready => 0,
+            GameState::Vowels(v) => v.current + 1,
+            _ => self.clue_count(),
+        }
+    }
+
+    fn is_guessable(&self, state: &GameState) -> bool {
+        matches!(state, GameState::Vowels(_))
+    }
+
+    fn is_over(&self, state: &GameState) -> bool {
+        matches!(state, GameState::Vowels(v) if v.is_finished())
+    }
+
+    /// A point for getting the phrase that's up, then it's on to the next one
+    fn guess(&self, state: &GameState, guess: &str, rules: &Rules) -> Guess {
+        let mut progress = match state {
+            GameState::Vowels(v) => v.clone(),
+            _ => return Guess::Wrong,
+        };
+        let phrase = self.phrases.get(progress.current).cloned().unwrap_or_default();
+        if !progress.answer(self, guess, &rules.matcher) {
+            return Guess::Wrong;
+        }
+        Guess::Progress {
+            said: progress.and_next(format!("It was {}", phrase)),
+            state: GameState::Vowels(progress),
+            points: 1,
+        }
+    }
+
+    fn summary(&self) -> String {
+        format!("{} ({} phrases)", self.category, self.phrases.len())
+    }
+}
+
+impl NextClue for TextMissingVowels {
+    /// Starts on the first phrase, after that it gives up on the phrase that's up and moves
+    /// on to the next one
+    fn next_clue(&self, state: &GameState, _rules: &Rules) -> (Option<String>, GameState) {
+        match state {
+            GameState::Ready => {
+                let progress = VowelsProgress::new(self);
+                let puzzle = progress.current_puzzle().unwrap_or_default();
+                (Some(format!("Category: {}\n\n{}", self.category, puzzle)), GameState::Vowels(progress))
+            },
+            GameState::Vowels(progress) => {
+                let mut progress = progress.clone();
+                match progress.skip(self) {
+                    Some(phrase) => {
+                        let said = progress.and_next(format!("Nobody got it, it was ||{}||", phrase));
+                        (Some(said), GameState::Vowels(progress))
+                    },
+                    None => (None, state.clone()),
+                }
+            },
+            _ => (None, state.clone()),
+        }
+    }
+}
+
+impl RevealAnswer for TextMissingVowels {
+    fn reveal_answer(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl Display for TextMissingVowels {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Missing vowels: {}\n", self.category)?;
+        for (i, p) in self.phrases.iter().enumerate() {
+            writeln!(f, "    {}. {} - ||{}||", i + 1, strip_vowels(p), p)?;
+        }
+        Ok(())
+    }
+}
+
+/// Uppercases a phrase and keeps only the consonants and digits, eg. `"Only Connect"` becomes
+/// `"NLYCNNCT"`. Accents come off first so accented vowels go too.
+pub fn strip_vowels(phrase: &str) -> String {
+    strip_accents(phrase)
+        .to_uppercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() && !VOWELS.contains(c))
+        .collect()
+}
+
+/// Strips the vowels and breaks what's left into random chunks of one to four letters, so the
+/// spaces don't give the words away
+pub fn puzzle(phrase: &str) -> String {
+    let letters = strip_vowels(phrase).chars().collect::<Vec<char>>();
+    let mut rng = thread_rng();
+    let mut chunks = vec![];
+    let mut start = 0;
+    while start < letters.len() {
+        let end = (start + rng.gen_range(1..=4)).min(letters.len());
+        chunks.push(letters[start..end].iter().collect::<String>());
+        start = end;
+    }
+    chunks.join(" ")
+}
+
+/// How far through the phrases the players are
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all="kebab-case")]
+pub struct VowelsProgress {
+    /// One puzzle per phrase, made when the round started
+    pub puzzles: Vec<String>,
+    /// Which phrase is up
+    pub current: usize,
+}
+
+impl VowelsProgress {
+    pub fn new(game: &TextMissingVowels) -> VowelsProgress {
+        VowelsProgress {
+            puzzles: game.phrases.iter().map(|p| puzzle(p)).collect(),
+            current: 0,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.current >= self.puzzles.len()
+    }
+
+    /// The puzzle that's up, numbered, eg. `"2/4: NLY CNN CT"`
+    pub fn current_puzzle(&self) -> Option<String> {
+        self.puzzles
+            .get(self.current)
+            .map(|p| format!("{}/{}: {}", self.current + 1, self.puzzles.len(), p))
+    }
+
+    /// Checks a guess against the phrase that's up, moving on to the next one if it's right
+    pub fn answer(&mut self, game: &TextMissingVowels, guess: &str, matcher: &Matcher) -> bool {
+        let right = game.phrases
+            .get(self.current)
+            .map(|p| matcher.matches(guess, p))
+            .unwrap_or(false);
+        if right {
+            self.current += 1;
+        }
+        right
+    }
+
+    /// What to post after `said` about the phrase that's just gone - the next puzzle, or that
+    /// that's all of them
+    pub fn and_next(&self, said: String) -> String {
+        match self.current_puzzle() {
+            Some(puzzle) => format!("{}\n\nNext up\n\n>>> {}", said, puzzle),
+            None => format!("{}\n\nThat's all of them!", said),
+        }
+    }
+
+    /// Gives up on the phrase that's up, handing back what it was
+    pub fn skip(&mut self, game: &TextMissingVowels) -> Option<String> {
+        let phrase = game.phrases.get(self.current).cloned()?;
+        self.current += 1;
+        Some(phrase)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_vowels_keeps_consonants_and_digits() {
+        assert_eq!(strip_vowels("Only Connect"), "NLYCNNCT");
+        assert_eq!(strip_vowels("Catch-22"), "CTCH22");
+    }
+
+    #[test]
+    fn strip_vowels_strips_accented_vowels() {
+        assert_eq!(strip_vowels("Crème brûlée"), "CRMBRL");
+        assert_eq!(strip_vowels("Motörhead"), "MTRHD");
+    }
+
+    #[test]
+    fn guessing_moves_through_the_phrases() {
+        let game = TextMissingVowels {
+            category: "Bands".to_string(),
+            phrases: vec!["Only Connect".to_string(), "Motörhead".to_string()],
+        };
+        let rules = Rules {
+            matcher: Matcher::new(2),
+            points: vec![1],
+            wall_seconds: None,
+        };
+        let (_, state) = game.next_clue(&GameState::Ready, &rules);
+        assert!(game.is_guessable(&state));
+        assert!(matches!(game.guess(&state, "the wrong one", &rules), Guess::Wrong));
+        let state = match game.guess(&state, "only connect", &rules) {
+            Guess::Progress { state, points: 1, .. } => state,
+            other => panic!("expected progress, got {:?}", other),
+        };
+        assert!(!game.is_over(&state));
+        let (skipped, state) = game.next_clue(&state, &rules);
+        assert!(skipped.unwrap().contains("||Motörhead||"));
+        assert!(game.is_over(&state));
+    }
+
+    #[test]
+    fn puzzle_only_moves_the_spaces() {
+        let puzzle = puzzle("Only Connect");
+        assert_eq!(puzzle.replace(' ', ""), "NLYCNNCT");
+        assert!(puzzle.split(' ').all(|chunk| !chunk.is_empty() && chunk.len() <= 4));
+    }
+}
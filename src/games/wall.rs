@@ -0,0 +1,427 @@
+use std::fmt::{Formatter, Display, self};
+use rand::{
+    thread_rng,
+    seq::SliceRandom,
+};
+use serde::{Serialize, Deserialize};
+use crate::{
+    games::{
+        answer::{
+            Accepted,
+            Matcher,
+            also_accepted,
+            normalise,
+        },
+        game::GameState,
+        traits::{Playable, NextClue, RevealAnswer, Guess, Rules},
+    },
+    error::Error,
+    data::unix_now,
+};
+
+pub const GROUPS: usize = 4;
+pub const GROUP_SIZE: usize = 4;
+/// Mistakes allowed once two groups have been found
+pub const LIVES: u8 = 3;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag="wall-game-type", rename_all="kebab-case")]
+pub enum WallGame {
+    Text(TextWall)
+}
+
+/// Sixteen clues making up four groups of four, each with its own connection
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all="kebab-case")]
+pub struct TextWall {
+    pub groups: Vec<WallGroup>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all="kebab-case")]
+pub struct WallGroup {
+    pub clues: Vec<String>,
+    pub connection: String,
+    /// Other ways of writing the connection that count as right
+    #[serde(default, skip_serializing_if="Vec::is_empty")]
+    pub accepted: Vec<Accepted>,
+}
+
+impl WallGroup {
+    pub fn check_connection(&self, guess: &str, matcher: &Matcher) -> bool {
+        matcher.matches(guess, &self.connection)
+            || self.accepted.iter().any(|a| a.matches(guess, matcher))
+    }
+}
+
+impl Playable for TextWall {
+    fn prompt(&self) -> &'static str {
+        "Find the four groups of four with `!group`, then name the connections with `!guess`"
+    }
+
+    fn intro(&self) -> &'static str {
+        "Here's the wall"
+    }
+
+    fn clue_count(&self) -> usize {
+        GROUPS * GROUP_SIZE
+    }
+
+    /// Checks there are four groups of four, that nothing's blank and that no clue appears
+    /// twice, otherwise there'd be no telling which group someone meant
+    fn validate(&self) -> Result<(), Error> {
+        if self.groups.len() != GROUPS {
+            return Err(Error::ArgError(format!("A wall needs {} groups but this one has {}", GROUPS, self.groups.len())));
+        }
+        if let Some((i, g)) = self.groups.iter().enumerate().find(|(_, g)| g.clues.len() != GROUP_SIZE) {
+            return Err(Error::ArgError(format!("Group {} needs {} clues but has {}", i + 1, GROUP_SIZE, g.clues.len())));
+        }
+        for (i, g) in self.groups.iter().enumerate() {
+            if let Some(c) = g.clues.iter().position(|c| c.trim().is_empty()) {
+                return Err(Error::ArgError(format!("Clue {} in group {} is blank", c + 1, i + 1)));
+            }
+            if g.connection.trim().is_empty() {
+                return Err(Error::ArgError(format!("The connection for group {} is blank", i + 1)));
+            }
+        }
+        let mut clues = self.groups
+            .iter()
+            .flat_map(|g| g.clues.iter().map(|c| normalise(c)))
+            .collect::<Vec<String>>();
+        clues.sort();
+        match clues.windows(2).find(|w| w[0] == w[1]) {
+            Some(w) => Err(Error::ArgError(format!("`{}` is on the wall twice", w[0]))),
+            None => Ok(()),
+        }
+    }
+
+    /// Guesses are for the connections, so not until the groups are found
+    fn is_guessable(&self, state: &GameState) -> bool {
+        matches!(state, GameState::Wall(w) if w.stage == WallStage::Connections)
+    }
+
+    fn is_over(&self, state: &GameState) -> bool {
+        matches!(state, GameState::Wall(w) if w.all_named())
+    }
+
+    /// Names a connection for a point, with two more for whoever names the last one if the
+    /// players found every group
+    fn guess(&self, state: &GameState, guess: &str, rules: &Rules) -> Guess {
+        let mut progress = match state {
+            GameState::Wall(w) if w.stage == WallStage::Connections => w.clone(),
+            _ => return Guess::Wrong,
+        };
+        if progress.name_connection(self, guess, &rules.matcher).is_none() {
+            return Guess::Wrong;
+        }
+        let (points, said) = if progress.all_named() {
+            (if progress.frozen { 1 } else { 3 }, "That's every connection!".to_string())
+        } else {
+            (1, self.render(&progress))
+        };
+        Guess::Progress {
+            state: GameState::Wall(progress),
+            points,
+            said,
+        }
+    }
+
+    fn summary(&self) -> String {
+        self.groups
+            .iter()
+            .map(|g| g.connection.as_str())
+            .collect::<Vec<&str>>()
+            .join(", ")
+    }
+}
+
+impl NextClue for TextWall {
+    /// The whole wall goes up at once, and the timer starts when it does
+    fn next_clue(&self, state: &GameState, rules: &Rules) -> (Option<String>, GameState) {
+        match state {
+            GameState::Ready => {
+                let progress = WallProgress::new(rules.wall_seconds.map(|s| unix_now() + s));
+                (Some(self.render(&progress)), GameState::Wall(progress))
+            },
+            _ => (None, state.clone()),
+        }
+    }
+}
+
+impl RevealAnswer for TextWall {
+    fn reveal_answer(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl TextWall {
+    pub fn clue(&self, tile: Tile) -> &str {
+        &self.groups[tile.0].clues[tile.1]
+    }
+
+    /// The wall as it stands, found groups first (with their connections once the solving is
+    /// over) then whatever's left in a four by four grid
+    pub fn render(&self, progress: &WallProgress) -> String {
+        let found = progress.found
+            .iter()
+            .enumerate()
+            .map(|(i, g)| {
+                let clues = self.groups[*g].clues.join(" · ");
+                match progress.stage {
+                    WallStage::Solving => format!("{}. **{}**", i + 1, clues),
+                    _ if progress.named.contains(g) => format!("{}. **{}** - {}", i + 1, clues, self.groups[*g].connection),
+                    _ => format!("{}. **{}** - ?", i + 1, clues),
+                }
+            })
+            .collect::<Vec<String>>();
+        let remaining = progress.remaining()
+            .chunks(GROUP_SIZE)
+            .map(|row| row.iter()
+                 .map(|t| format!("`{}`", self.clue(*t)))
+                 .collect::<Vec<String>>()
+                 .join("  "))
+            .collect::<Vec<String>>();
+        let lives = match (&progress.stage, progress.lives) {
+            (WallStage::Solving, Some(l)) => format!("\nLives left: {}", l),
+            _ => String::new(),
+        };
+        [found, remaining].concat().join("\n") + &lives
+    }
+}
+
+impl Display for TextWall {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Find the four groups and name their connections\n")?;
+        for (i, g) in self.groups.iter().enumerate() {
+            writeln!(f, "    {}. {}", i + 1, g.clues.join(", "))?;
+            writeln!(f, "        Connection: ||{}||{}", g.connection, also_accepted(&g.accepted))?;
+        }
+        Ok(())
+    }
+}
+
+/// Which clue on the wall, as (group, clue within the group)
+pub type Tile = (usize, usize);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all="kebab-case")]
+pub enum WallStage {
+    /// Finding the groups
+    Solving,
+    /// Groups found (or the wall froze), naming the connections
+    Connections,
+}
+
+/// How far through a wall the players are
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all="kebab-case")]
+pub struct WallProgress {
+    /// The order the tiles were shuffled into
+    pub layout: Vec<Tile>,
+    /// Groups in the order they were found (or revealed when the wall froze)
+    pub found: Vec<usize>,
+    /// Groups whose connection has been named
+    pub named: Vec<usize>,
+    /// Mistakes left, only counted once two groups have been found
+    pub lives: Option<u8>,
+    pub stage: WallStage,
+    /// Unix timestamp (seconds) after which no more groups can be found, if there's a timer
+    pub deadline: Option<u64>,
+    /// Whether the players ran out of lives or time before finding every group
+    #[serde(default)]
+    pub frozen: bool,
+}
+
+/// What happened when someone put forward a group
+#[derive(Clone, Debug, PartialEq)]
+pub enum GroupResult {
+    Found(usize),
+    /// Found the third group, and so the last one too since it's whatever's left
+    Solved,
+    Wrong,
+    /// Out of lives, the wall's frozen
+    Frozen,
+}
+
+impl WallProgress {
+    pub fn new(deadline: Option<u64>) -> WallProgress {
+        let mut layout = (0..GROUPS)
+            .flat_map(|g| (0..GROUP_SIZE).map(move |c| (g, c)))
+            .collect::<Vec<Tile>>();
+        layout.shuffle(&mut thread_rng());
+        WallProgress {
+            layout,
+            found: vec![],
+            named: vec![],
+            lives: None,
+            stage: WallStage::Solving,
+            deadline,
+            frozen: false,
+        }
+    }
+
+    /// Tiles that aren't part of a found group, in wall order
+    pub fn remaining(&self) -> Vec<Tile> {
+        self.layout
+            .iter()
+            .filter(|(g, _)| !self.found.contains(g))
+            .copied()
+            .collect()
+    }
+
+    /// Works out which tiles a player meant, each guess has to match a different tile that's
+    /// still on the wall
+    pub fn find_tiles(&self, wall: &TextWall, guesses: &[String], matcher: &Matcher) -> Result<Vec<Tile>, Error> {
+        if guesses.len() != GROUP_SIZE {
+            return Err(Error::ArgError(format!("A group is {} clues, you gave {}", GROUP_SIZE, guesses.len())));
+        }
+        let remaining = self.remaining();
+        let mut tiles: Vec<Tile> = vec![];
+        for guess in guesses {
+            let exact = remaining.iter()
+                .find(|t| !tiles.contains(t) && normalise(wall.clue(**t)) == normalise(guess));
+            let close = || remaining.iter()
+                .find(|t| !tiles.contains(t) && matcher.matches(guess, wall.clue(**t)));
+            let tile = exact.or_else(close)
+                .ok_or_else(|| Error::ArgError(format!("Couldn't find `{}` on the wall", guess)))?;
+            tiles.push(*tile);
+        }
+        Ok(tiles)
+    }
+
+    pub fn submit_group(&mut self, tiles: &[Tile]) -> GroupResult {
+        let group = tiles[0].0;
+        if tiles.iter().all(|(g, _)| *g == group) {
+            self.found.push(group);
+            if self.found.len() == GROUPS - 1 {
+                // Whatever's left has to be the last group
+                let last = (0..GROUPS).find(|g| !self.found.contains(g));
+                self.found.extend(last);
+            }
+            if self.found.len() >= 2 && self.lives.is_none() {
+                self.lives = Some(LIVES);
+            }
+            if self.found.len() == GROUPS {
+                self.stage = WallStage::Connections;
+                return GroupResult::Solved;
+            }
+            return GroupResult::Found(group);
+        }
+        match self.lives {
+            Some(l) if l <= 1 => {
+                self.lives = Some(0);
+                self.freeze();
+                GroupResult::Frozen
+            },
+            Some(l) => {
+                self.lives = Some(l - 1);
+                GroupResult::Wrong
+            },
+            None => GroupResult::Wrong,
+        }
+    }
+
+    /// Stops the solving, the groups nobody found are shown so their connections can still
+    /// be named
+    pub fn freeze(&mut self) {
+        let missing = (0..GROUPS)
+            .filter(|g| !self.found.contains(g))
+            .collect::<Vec<usize>>();
+        self.found.extend(missing);
+        self.stage = WallStage::Connections;
+        self.frozen = true;
+    }
+
+    /// Names whichever unnamed connection the guess matches, if any
+    pub fn name_connection(&mut self, wall: &TextWall, guess: &str, matcher: &Matcher) -> Option<usize> {
+        let group = (0..GROUPS)
+            .find(|g| !self.named.contains(g) && wall.groups[*g].check_connection(guess, matcher))?;
+        self.named.push(group);
+        Some(group)
+    }
+
+    pub fn all_named(&self) -> bool {
+        self.named.len() == GROUPS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wall() -> TextWall {
+        let group = |clues: [&str; 4], connection: &str| WallGroup {
+            clues: clues.iter().map(|c| c.to_string()).collect(),
+            connection: connection.to_string(),
+            accepted: vec![],
+        };
+        TextWall {
+            groups: vec![
+                group(["Mercury", "Venus", "Mars", "Saturn"], "Planets"),
+                group(["Red", "Blue", "Green", "Yellow"], "Colours"),
+                group(["Oak", "Ash", "Elm", "Yew"], "Trees"),
+                group(["Python", "Rust", "Go", "Java"], "Programming languages"),
+            ],
+        }
+    }
+
+    fn rules() -> Rules {
+        Rules {
+            matcher: Matcher::new(2),
+            points: vec![1],
+            wall_seconds: None,
+        }
+    }
+
+    #[test]
+    fn walls_need_four_groups_of_four() {
+        let mut short = wall();
+        short.groups.pop();
+        assert!(wall().validate().is_ok());
+        assert!(short.validate().is_err());
+        let mut thin = wall();
+        thin.groups[2].clues.pop();
+        assert!(thin.validate().is_err());
+    }
+
+    #[test]
+    fn walls_cant_have_blanks() {
+        let mut blank_clue = wall();
+        blank_clue.groups[1].clues[3] = "  ".to_string();
+        assert!(blank_clue.validate().is_err());
+        let mut blank_connection = wall();
+        blank_connection.groups[0].connection = String::new();
+        assert!(blank_connection.validate().is_err());
+    }
+
+    #[test]
+    fn naming_every_connection_ends_the_wall() {
+        let wall = wall();
+        let rules = rules();
+        let (_, state) = wall.next_clue(&GameState::Ready, &rules);
+        assert!(!wall.is_guessable(&state));
+        let mut progress = match state {
+            GameState::Wall(p) => p,
+            other => panic!("expected a wall, got {:?}", other),
+        };
+        for g in 0..3 {
+            let tiles = (0..GROUP_SIZE).map(|c| (g, c)).collect::<Vec<Tile>>();
+            progress.submit_group(&tiles);
+        }
+        assert_eq!(progress.stage, WallStage::Connections);
+        let mut state = GameState::Wall(progress);
+        assert!(wall.is_guessable(&state));
+        assert!(matches!(wall.guess(&state, "rivers", &rules), Guess::Wrong));
+        for (i, connection) in ["planets", "colours", "trees", "programming languages"].iter().enumerate() {
+            assert!(!wall.is_over(&state));
+            state = match wall.guess(&state, connection, &rules) {
+                Guess::Progress { state, points, .. } => {
+                    assert_eq!(points, if i == 3 { 3 } else { 1 });
+                    state
+                },
+                other => panic!("expected progress, got {:?}", other),
+            };
+        }
+        assert!(wall.is_over(&state));
+    }
+}
@@ -0,0 +1,254 @@
+use std::{
+    fmt::{Formatter, Display, self},
+    str::FromStr,
+};
+use serde::{Serialize, Deserialize};
+use serenity::model::id::{
+    ChannelId,
+    RoleId,
+};
+use crate::{
+    games::game::GameType,
+    scores::Scoring,
+    error::Error,
+};
+
+/// Settings an admin can change for their own guild, these are saved to `guilds.json` in the
+/// data directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all="kebab-case")]
+pub struct GuildConfig {
+    /// The only channel games can be played in, anywhere if it's not set
+    #[serde(default)]
+    pub main_channel: Option<ChannelId>,
+    /// How many typos a guess can have and still be right
+    #[serde(default="default_guess_tolerance")]
+    pub guess_tolerance: usize,
+    /// Whether `||spoilered||` messages in the game channel count as guesses
+    #[serde(default)]
+    pub capture_spoilers: bool,
+    #[serde(default)]
+    pub scoring: Scoring,
+    /// How long players get to find the groups on a wall, no limit if it's not set
+    #[serde(default)]
+    pub wall_seconds: Option<u64>,
+    /// Whether rounds ping whoever submitted the game, otherwise they just get their name
+    #[serde(default)]
+    pub mention_submitters: bool,
+    /// Whether only games a moderator has approved get played
+    #[serde(default)]
+    pub moderated: bool,
+    /// Which discord roles can use which commands
+    #[serde(default)]
+    pub roles: Roles,
+    /// How long rounds wait for a right answer before moving on by themselves
+    #[serde(default)]
+    pub clue_timers: ClueTimers,
+    /// Minutes a round can go without anyone doing anything before its answer is revealed, it
+    /// can go on forever if it's not set
+    #[serde(default)]
+    pub idle_minutes: Option<u64>,
+}
+
+fn default_guess_tolerance() -> usize {
+    2
+}
+
+impl Default for GuildConfig {
+    fn default() -> GuildConfig {
+        GuildConfig {
+            main_channel: None,
+            guess_tolerance: default_guess_tolerance(),
+            capture_spoilers: false,
+            scoring: Scoring::default(),
+            wall_seconds: None,
+            mention_submitters: false,
+            moderated: false,
+            roles: Roles::default(),
+            clue_timers: ClueTimers::default(),
+            idle_minutes: None,
+        }
+    }
+}
+
+/// Seconds to wait for a right answer before showing the next clue (or in missing vowels, the
+/// next phrase) for each type of game. After the last clue it's how long until the answer is
+/// revealed. Rounds of a type that isn't set aren't timed, walls have their own timer.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all="kebab-case")]
+pub struct ClueTimers {
+    #[serde(default)]
+    pub link: Option<u64>,
+    #[serde(default)]
+    pub sequence: Option<u64>,
+    #[serde(default)]
+    pub missing_vowels: Option<u64>,
+}
+
+impl ClueTimers {
+    pub fn seconds(&self, game: &GameType) -> Option<u64> {
+        match game {
+            GameType::Link(_) => self.link,
+            GameType::Sequence(_) => self.sequence,
+            GameType::MissingVowels(_) => self.missing_vowels,
+            GameType::Wall(_) => None,
+        }
+    }
+
+    pub fn seconds_mut(&mut self, game_type: &str) -> Result<&mut Option<u64>, Error> {
+        match game_type {
+            "link" => Ok(&mut self.link),
+            "sequence" | "seq" => Ok(&mut self.sequence),
+            "vowels" | "missing-vowels" => Ok(&mut self.missing_vowels),
+            _ => Err(Error::ArgError(format!("`{}` should be link, sequence or vowels", game_type))),
+        }
+    }
+}
+
+/// What someone's allowed to do, each one can do everything the ones before it can
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    /// Guessing and looking at scores
+    Player,
+    /// Adding games and editing their own
+    Submitter,
+    /// Running rounds and looking after the moderation queue
+    Quizmaster,
+    /// Changing the guild's settings
+    Admin,
+}
+
+impl Role {
+    const ALL: [Role; 4] = [Role::Player, Role::Submitter, Role::Quizmaster, Role::Admin];
+}
+
+impl FromStr for Role {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Role, Error> {
+        match s {
+            "player" => Ok(Role::Player),
+            "submitter" => Ok(Role::Submitter),
+            "quizmaster" => Ok(Role::Quizmaster),
+            "admin" => Ok(Role::Admin),
+            _ => Err(Error::ArgError(format!("`{}` should be one of player, submitter, quizmaster or admin", s))),
+        }
+    }
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Role::Player => write!(f, "player"),
+            Role::Submitter => write!(f, "submitter"),
+            Role::Quizmaster => write!(f, "quizmaster"),
+            Role::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+/// The discord role that goes with each `Role`. A level that isn't set goes by the nearest one
+/// below it that is, and anyone can do what needs a level with nothing set at or below it. Admin
+/// is the exception, it's left to people with the Administrator permission until it's set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all="kebab-case")]
+pub struct Roles {
+    #[serde(default)]
+    pub player: Option<RoleId>,
+    #[serde(default)]
+    pub submitter: Option<RoleId>,
+    #[serde(default)]
+    pub quizmaster: Option<RoleId>,
+    #[serde(default)]
+    pub admin: Option<RoleId>,
+}
+
+impl Roles {
+    pub fn get(&self, role: Role) -> Option<RoleId> {
+        match role {
+            Role::Player => self.player,
+            Role::Submitter => self.submitter,
+            Role::Quizmaster => self.quizmaster,
+            Role::Admin => self.admin,
+        }
+    }
+
+    pub fn set(&mut self, role: Role, role_id: Option<RoleId>) {
+        match role {
+            Role::Player => self.player = role_id,
+            Role::Submitter => self.submitter = role_id,
+            Role::Quizmaster => self.quizmaster = role_id,
+            Role::Admin => self.admin = role_id,
+        }
+    }
+
+    /// Whether someone with `member_roles` can do what needs `role`. Administrators can do
+    /// anything, and so can anyone with the discord role for the level `role` goes by or one
+    /// above it.
+    pub fn allows(&self, role: Role, member_roles: &[RoleId], administrator: bool) -> bool {
+        if administrator {
+            return true;
+        }
+        let required = match self.required(role) {
+            Some(r) => r,
+            None => return role != Role::Admin,
+        };
+        Role::ALL.iter()
+            .filter(|r| **r >= required)
+            .filter_map(|r| self.get(*r))
+            .any(|id| member_roles.contains(&id))
+    }
+
+    /// The nearest level at or below `role` that has a discord role set, admin only ever goes
+    /// by its own
+    fn required(&self, role: Role) -> Option<Role> {
+        if role == Role::Admin {
+            return self.admin.map(|_| Role::Admin);
+        }
+        Role::ALL.iter()
+            .rev()
+            .copied()
+            .filter(|r| *r <= role)
+            .find(|r| self.get(*r).is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAYER: RoleId = RoleId(1);
+    const QUIZMASTER: RoleId = RoleId(3);
+    const ADMIN: RoleId = RoleId(4);
+
+    #[test]
+    fn anyone_can_do_anything_but_admin_with_nothing_set() {
+        let roles = Roles::default();
+        assert!(roles.allows(Role::Quizmaster, &[], false));
+        assert!(!roles.allows(Role::Admin, &[], false));
+        assert!(roles.allows(Role::Admin, &[], true));
+    }
+
+    #[test]
+    fn unset_levels_go_by_the_nearest_one_below() {
+        let mut roles = Roles::default();
+        roles.set(Role::Player, Some(PLAYER));
+        assert!(roles.allows(Role::Submitter, &[PLAYER], false));
+        assert!(roles.allows(Role::Quizmaster, &[PLAYER], false));
+        assert!(!roles.allows(Role::Quizmaster, &[], false));
+        assert!(!roles.allows(Role::Admin, &[PLAYER], false));
+    }
+
+    #[test]
+    fn higher_roles_can_do_what_lower_ones_can() {
+        let mut roles = Roles::default();
+        roles.set(Role::Player, Some(PLAYER));
+        roles.set(Role::Quizmaster, Some(QUIZMASTER));
+        roles.set(Role::Admin, Some(ADMIN));
+        assert!(roles.allows(Role::Player, &[QUIZMASTER], false));
+        assert!(roles.allows(Role::Submitter, &[PLAYER], false));
+        assert!(!roles.allows(Role::Quizmaster, &[PLAYER], false));
+        assert!(roles.allows(Role::Quizmaster, &[ADMIN], false));
+        assert!(!roles.allows(Role::Admin, &[QUIZMASTER], false));
+    }
+}
@@ -1,3 +1,10 @@
 pub mod games;
 pub mod data;
 pub mod error;
+pub mod persist;
+pub mod store;
+pub mod session;
+pub mod guild;
+pub mod scores;
+pub mod teams;
+pub mod wizard;
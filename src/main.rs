@@ -1,14 +1,31 @@
-use std::collections::HashSet;
+use std::{
+    collections::HashSet,
+    sync::atomic::{AtomicBool, Ordering},
+    path::{Path, PathBuf},
+    time::Duration,
+};
 use serenity::{
+    async_trait,
     prelude::TypeMap,
+    http::Http,
     client::{
         Client, 
         Context,
         EventHandler,
     },
+    futures::StreamExt,
     model::{
-        channel::Message,
-        id::UserId,
+        channel::{
+            Message,
+            ReactionType,
+        },
+        gateway::Ready,
+        id::{
+            ChannelId,
+            GuildId,
+            RoleId,
+            UserId,
+        },
     },
     framework::{
         standard::{
@@ -29,16 +46,26 @@ use serenity::{
 use nancy::{
     data::{
         State,
+        unix_now,
+        Solved,
         Executor,
         ResponseOk,
         ResponseErr,
         Respondable,
         OrSend,
+        OrLog,
     },
     games::{
         game::{
             Game,
             GameType,
+            GameState,
+            PlayingGame,
+            Review,
+        },
+        answer::{
+            Accepted,
+            spoiler,
         },
         link::{
             LinkGame,
@@ -48,29 +75,117 @@ use nancy::{
             SequenceGame,
             TextSequence,
         },
+        media::{
+            ImageUpload,
+            IMAGE_MARKER,
+            NO_ALT_TEXT,
+            image_marker,
+        },
+        vowels::{
+            MissingVowelsGame,
+            TextMissingVowels,
+        },
+        wall::{
+            WallGame,
+            TextWall,
+            WallGroup,
+            GroupResult,
+            GROUPS,
+            GROUP_SIZE,
+        },
+        traits::Playable,
     },
     error::Error,
+    scores::{
+        PlayerScore,
+        ranked,
+    },
+    session::SessionKey,
+    guild::Role,
+    wizard::Wizard,
+    store::{
+        GameStore,
+        ExhaustedPolicy,
+        JsonDirStore,
+        SqliteStore,
+    },
 };
 
-/// Prints the number of games Nancy knows about
+/// Prints the number of games Nancy knows about, and how many haven't been played yet
 #[command]
 async fn status(ctx: &Context, msg: &Message) -> CommandResult {
+    require(ctx, msg, Role::Player).await.or_send().await?;
     Executor::new(ctx, msg)
-        .read(|s| {
-            log::info!("Number of games: {}", s.games.len());
-            ResponseOk::new(ctx, msg)
-                .with_content(format!("Number of games: {}", s.games.len()))
+        .try_get(|s| {
+            let stats = s.stats()?;
+            log::info!("Number of games: {}", stats.total);
+            Ok(ResponseOk::new(ctx, msg)
+                .with_content(format!(
+                    "Number of games: {} ({} links, {} sequences, {} walls, {} missing vowels, {} not played yet, {} waiting for approval)",
+                    stats.total, stats.links, stats.sequences, stats.walls, stats.missing_vowels, stats.unplayed, stats.pending)))
         })
         .await
         .send()
         .await
 }
 
-/// Adds a game encoded in json - not a very friendly way of adding games
+/// Checks the author has `role` (or one above it) in this guild, or in DMs the home guild
+async fn require<'a>(ctx: &'a Context, msg: &'a Message, role: Role) -> Result<(), ResponseErr<'a>> {
+    if is_allowed(ctx, msg.guild_id, msg.author.id, role).await {
+        Ok(())
+    } else {
+        Err(ResponseErr::new(ctx, msg, Error::NotAllowed(role)))
+    }
+}
+
+/// Checks the author is one of the people running Nancy, for anything that changes the games
+/// every guild shares
+async fn require_owner<'a>(ctx: &'a Context, msg: &'a Message) -> Result<(), ResponseErr<'a>> {
+    let owner = Executor::new(ctx, msg)
+        .get(|s| s.is_owner(msg.author.id))
+        .await?;
+    if owner {
+        Ok(())
+    } else {
+        Err(ResponseErr::new(ctx, msg, Error::OwnersOnly))
+    }
+}
+
+/// Whether `user_id` has `role` in `guild_id`. DMs (`None`) go by the roles in the home guild
+/// set with `NANCY_HOME_GUILD`, without one they go by the defaults (anything but admin).
+/// Anyone who isn't in the guild is treated as having no roles in it.
+async fn is_allowed(ctx: &Context, guild_id: Option<GuildId>, user_id: UserId, role: Role) -> bool {
+    let (guild_id, roles) = match ctx.data.read().await.get::<State>() {
+        Some(s) => {
+            let guild_id = guild_id.or(s.home_guild);
+            (guild_id, s.guild(guild_id).roles)
+        },
+        None => return false,
+    };
+    let member = match guild_id {
+        Some(g) => g.member(ctx, user_id).await.ok(),
+        None => None,
+    };
+    let administrator = match &member {
+        Some(m) => m.permissions(ctx)
+            .await
+            .map(|p| p.administrator())
+            .unwrap_or(false),
+        None => false,
+    };
+    let member_roles = member
+        .map(|m| m.roles)
+        .unwrap_or_default();
+    roles.allows(role, &member_roles, administrator)
+}
+
+/// Adds a game encoded in json - not a very friendly way of adding games. The json can say
+/// anything about the game, so only whoever runs Nancy can do it.
 #[command]
 #[only_in("dm")]
 #[aliases("add-game")]
 async fn add_game(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require_owner(ctx, msg).await.or_send().await?;
     let data = args.rest();
     let dm_chan = msg.author.create_dm_channel(ctx).await?;
     Executor::new(ctx, msg)
@@ -78,9 +193,8 @@ async fn add_game(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
             let game: GameType = serde_json::from_str(&data)
                 .map_err(|e| Error::Serde(format!("{}", e)))?;
             let reply_msg = format!("```\n{:?}\n```", &game);
-            let submitted_by = msg.author.name.clone();
-            let game = Game { submitted_by, game };
-            s.add_game(game);
+            let game = Game::new(&msg.author, game);
+            s.add_game(game)?;
             Ok(ResponseOk::new(ctx, msg)
                 .with_dm_channel(&dm_chan)
                 .with_content(reply_msg)
@@ -91,8 +205,98 @@ async fn add_game(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
         .await
 }
 
+/// Swaps clues marked `[image]` for the pictures attached to the message (in the same order),
+/// downloading them ready to save once the game's been checked. Whatever comes after the marker
+/// is the alt text, which is shown as the clue.
+async fn image_clues<'a>(ctx: &'a Context, msg: &'a Message, clues: &mut [String]) -> Result<Vec<ImageUpload>, ResponseErr<'a>> {
+    let wanted = clues.iter()
+        .enumerate()
+        .filter_map(|(i, c)| image_marker(c).map(|alt| (i + 1, alt)))
+        .collect::<Vec<(usize, Option<String>)>>();
+    if wanted.len() != msg.attachments.len() {
+        let m = format!("{} clues are marked {} but there are {} pictures attached", wanted.len(), IMAGE_MARKER, msg.attachments.len());
+        return Err(ResponseErr::new(ctx, msg, Error::ArgError(m)));
+    }
+    let mut uploads = vec![];
+    for ((clue, alt), attachment) in wanted.into_iter().zip(msg.attachments.iter()) {
+        if attachment.width.is_none() {
+            let m = format!("`{}` isn't a picture", attachment.filename);
+            return Err(ResponseErr::new(ctx, msg, Error::ArgError(m)));
+        }
+        let extension = Path::new(&attachment.filename)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("png")
+            .to_lowercase();
+        let contents = attachment.download()
+            .await
+            .map_err(|e| ResponseErr::new(ctx, msg, Error::Io(format!("Couldn't download {}: {}", attachment.filename, e))))?;
+        clues[clue - 1] = alt.clone().unwrap_or_else(|| NO_ALT_TEXT.to_string());
+        uploads.push(ImageUpload { clue, alt, extension, contents });
+    }
+    Ok(uploads)
+}
+
+/// Reads the clues for a link or sequence game and then the answer, with any lines after that
+/// being other ways of writing the answer. There are `default` clues unless the first line says
+/// otherwise, like `6 clues`.
+fn clue_args<'a>(ctx: &'a Context, msg: &'a Message, args: &mut Args, default: usize) -> Result<(Vec<String>, String, Vec<Accepted>), ResponseErr<'a>> {
+    let count = args.current()
+        .and_then(|a| a.trim().strip_suffix(" clues"))
+        .and_then(|n| n.trim().parse::<usize>().ok());
+    if count.is_some() {
+        args.advance();
+    }
+    let count = count.unwrap_or(default);
+    let mut clues: Vec<String> = vec![];
+    let got = |clues: &[String]| clues.iter()
+        .enumerate()
+        .map(|(i, c)| format!("`clue{}={}`", i + 1, c))
+        .collect::<Vec<String>>()
+        .join(", ");
+    while clues.len() < count {
+        let clue = args.single::<String>()
+            .map_err(|_| {
+                let m = match clues.len() {
+                    0 => "Couldn't get clue1, try again".to_string(),
+                    n => format!("I got {}, but couldn't get clue{}", got(&clues), n + 1),
+                };
+                ResponseErr::new(ctx, msg, Error::ArgError(m))
+            })?;
+        clues.push(clue);
+    }
+    let answer = args.single::<String>()
+        .map_err(|_| {
+            let m = format!("I got the clues {}, but couldn't get the answer", got(&clues));
+            ResponseErr::new(ctx, msg, Error::ArgError(m))
+        })?;
+    let accepted = args.iter::<String>()
+        .filter_map(|a| a.ok())
+        .map(|a| Accepted::parse(&a))
+        .collect();
+    Ok((clues, answer, accepted))
+}
+
+/// Takes the `pattern: ...` line (if there is one) out of the lines after a sequence's answer
+fn take_pattern(accepted: &mut Vec<Accepted>) -> Option<String> {
+    let pattern = |a: &Accepted| match a {
+        Accepted::Text(line) => line.split_once(':')
+            .filter(|(key, _)| key.trim().eq_ignore_ascii_case("pattern"))
+            .map(|(_, p)| p.trim().to_string())
+            .filter(|p| !p.is_empty()),
+        Accepted::Regex { .. } => None,
+    };
+    let index = accepted.iter().position(|a| pattern(a).is_some())?;
+    pattern(&accepted.remove(index))
+}
+
 /// Adds a text-based "guess the link" game, expects five arguments (separated by a newline - shift+enter) for the
-/// four clues and the answer.
+/// four clues and the answer. Any lines after that are other ways of writing the answer that should count as
+/// right, put a regex between slashes (like `/(the )?classical elements/`) for anything fiddly.
+///
+/// For a picture clue, attach the picture and write `[image]` for that clue, followed by some alt text if you like.
+///
+/// For a sprint or a marathon, start with how many clues there are (between three and six), eg. `3 clues`.
 ///
 /// Eg.
 ///
@@ -107,51 +311,25 @@ async fn add_game(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
 #[only_in("dm")]
 #[aliases("add-text-link-game", "add-link", "addlink", "add_link", "link")]
 async fn add_text_link_game(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require(ctx, msg, Role::Submitter).await.or_send().await?;
     let mut args = args;
-    let clue1: String = args.single()
-        .or_else(|_| {
-            let m = format!("Could't get clue1, try again");
-            Err(ResponseErr::new(ctx, msg, Error::ArgError(m)))
-        })
-        .or_send()
-        .await?;
-    let clue2: String = args.single()
-        .or_else(|_| {
-            let m = format!("I got `clue1={}`, but couldn't get clue2", &clue1);
-            Err(ResponseErr::new(ctx, msg, Error::ArgError(m)))
-        })
-        .or_send()
-        .await?;
-    let clue3: String = args.single()
-        .or_else(|_| {
-            let m = format!("I got `clue1={}` and `clue2={}`, but couldn't get clue3", &clue1, &clue2);
-            Err(ResponseErr::new(ctx, msg, Error::ArgError(m)))
-        })
-        .or_send()
-        .await?;
-    let clue4: String = args.single()
-        .or_else(|_| {
-            let m = format!("I got `clue1={}`, `clue2={}` and `clue3={}`, but couldn't get clue4", &clue1, &clue2, &clue3);
-            Err(ResponseErr::new(ctx, msg, Error::ArgError(m)))
-        })
+    let (mut clues, answer, accepted) = clue_args(ctx, msg, &mut args, 4)
         .or_send()
         .await?;
-    let answer: String = args.single()
-        .or_else(|_| {
-            let m = format!("I got the clues `clue1={}`, `clue2={}`, `clue3={}` and `clue4={}`, but couldn't get the answer", &clue1, &clue2, &clue3, &clue4);
-            Err(ResponseErr::new(ctx, msg, Error::ArgError(m)))
-        })
+    let uploads = image_clues(ctx, msg, &mut clues)
+        .await
         .or_send()
         .await?;
-    let text_link_game = TextLink { clue1, clue2, clue3, clue4, answer };
-    let submitted_by = msg.author.name.clone();
-    let game = Game { submitted_by, game: GameType::Link(LinkGame::Text(text_link_game)) };
     Executor::new(ctx, msg)
-        .write(|s| {
+        .try_write(|s| {
+            let mut text_link_game = TextLink { clues, answer, accepted, images: vec![] };
+            text_link_game.validate()?;
+            text_link_game.images = s.save_images(&uploads)?;
+            let game = Game::new(&msg.author, GameType::Link(LinkGame::Text(text_link_game)));
             let game_str = format!("```\n{}\n```", &game);
-            s.add_game(game);
-            ResponseOk::new(ctx, msg)
-                .with_content(format!("Added game:\n{}", game_str))
+            s.add_game(game)?;
+            Ok(ResponseOk::new(ctx, msg)
+                .with_content(format!("Added game:\n{}", game_str)))
         })
         .await
         .send()
@@ -159,7 +337,14 @@ async fn add_text_link_game(ctx: &Context, msg: &Message, args: Args) -> Command
 }
 
 /// Adds a text-based "guess the sequence" game, expects six arguments (separated by a newline - shift+enter) for the
-/// five clues and the answer.
+/// five clues and the answer. Any lines after that are other ways of writing the last clue that should count as
+/// right, put a regex between slashes (like `/u(niform)?/`) for anything fiddly. A `pattern: ...` line gives a short
+/// name for the pattern, which counts as right too.
+///
+/// For a picture clue, attach the picture and write `[image]` for that clue, followed by some alt text if you like.
+///
+/// For a sprint or a marathon, start with how many clues there are counting the one to guess (between three and
+/// seven), eg. `7 clues`.
 ///
 /// Eg.
 ///
@@ -169,70 +354,344 @@ async fn add_text_link_game(ctx: &Context, msg: &Message, args: Args) -> Command
 ///     i
 ///     o
 ///     u
-///     vowels
+///     the vowels in alphabetical order
+///     pattern: vowels
 ///
 #[command]
 #[only_in("dm")]
 #[aliases("add-text-sequence-game", "add-sequence", "add_sequence", "addsequence", "sequence", "seq", "add-seq", "add_seq")]
 async fn add_text_sequence_game(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require(ctx, msg, Role::Submitter).await.or_send().await?;
     let mut args = args;
-    let clue1: String = args.single()
-        .or_else(|_| {
-            let m = format!("Could't get clue1, try again");
-            Err(ResponseErr::new(ctx, msg, Error::ArgError(m)))
-        })
+    let (mut clues, answer, mut accepted) = clue_args(ctx, msg, &mut args, 5)
         .or_send()
         .await?;
-    let clue2: String = args.single()
-        .or_else(|_| {
-            let m = format!("I got `clue1={}`, but couldn't get clue2", &clue1);
-            Err(ResponseErr::new(ctx, msg, Error::ArgError(m)))
-        })
+    let pattern = take_pattern(&mut accepted);
+    let uploads = image_clues(ctx, msg, &mut clues)
+        .await
         .or_send()
         .await?;
-    let clue3: String = args.single()
-        .or_else(|_| {
-            let m = format!("I got `clue1={}` and `clue2={}`, but couldn't get clue3", &clue1, &clue2);
-            Err(ResponseErr::new(ctx, msg, Error::ArgError(m)))
+    Executor::new(ctx, msg)
+        .try_write(|s| {
+            let mut text_seq_game = TextSequence { clues, answer, accepted, pattern, images: vec![] };
+            text_seq_game.validate()?;
+            text_seq_game.images = s.save_images(&uploads)?;
+            let game = Game::new(&msg.author, GameType::Sequence(SequenceGame::Text(text_seq_game)));
+            let game_str = format!("```\n{}\n```", &game);
+            s.add_game(game)?;
+            Ok(ResponseOk::new(ctx, msg)
+                .with_content(format!("Added game:\n{}", game_str)))
         })
+        .await
+        .send()
+        .await
+}
+
+/// Walks you through adding a game one question at a time - pick the type, give the clues one
+/// by one, then check it over and fix anything before it's added. Send `cancel` at any point to
+/// stop.
+#[command]
+#[only_in("dm")]
+#[aliases("new-game", "new", "submit", "wizard")]
+async fn new_game(ctx: &Context, msg: &Message) -> CommandResult {
+    require(ctx, msg, Role::Submitter).await.or_send().await?;
+    let wizard = Wizard::new(ctx, msg);
+    let game = async {
+        let game = wizard.ask_game().await?;
+        wizard.review(game).await
+    }
+        .await
+        .map_err(|e| ResponseErr::new(ctx, msg, e))
         .or_send()
         .await?;
-    let clue4: String = args.single()
-        .or_else(|_| {
-            let m = format!("I got `clue1={}`, `clue2={}` and `clue3={}`, but couldn't get clue4", &clue1, &clue2, &clue3);
-            Err(ResponseErr::new(ctx, msg, Error::ArgError(m)))
+    let game = Game::new(&msg.author, game);
+    Executor::new(ctx, msg)
+        .try_write(|s| {
+            let game = s.add_game(game)?;
+            Ok(ResponseOk::new(ctx, msg)
+                .with_content(format!("Added game `{}`, thanks!", game.id)))
+        })
+        .await
+        .send()
+        .await
+}
+
+/// Lists the games you've submitted, with their ids for `!edit` and `!delete`
+#[command]
+#[only_in("dm")]
+#[aliases("my-games")]
+async fn my_games(ctx: &Context, msg: &Message) -> CommandResult {
+    require(ctx, msg, Role::Submitter).await.or_send().await?;
+    Executor::new(ctx, msg)
+        .try_get(|s| {
+            let games = s.games_by(&msg.author)?;
+            let content = if games.is_empty() {
+                "You haven't submitted any games yet".to_string()
+            } else {
+                games.iter()
+                    .map(|g| {
+                        let review = match &g.review {
+                            Review::Approved => String::new(),
+                            Review::Pending => " - waiting for approval".to_string(),
+                            Review::Rejected(reason) => format!(" - rejected: {}", reason),
+                        };
+                        format!("`{}` {} - {} (played {} times){}", g.id, g.game.kind(), g.game.playable().summary(), g.plays, review)
+                    })
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            };
+            Ok(ResponseOk::new(ctx, msg)
+                .with_content(content))
         })
+        .await
+        .send()
+        .await
+}
+
+/// Shows one of your games and lets you change it, the same way as when adding one with
+/// `!new-game`
+///
+/// Eg.
+///
+///     !edit 17b2c9a41e0-3f2a
+///
+#[command]
+#[only_in("dm")]
+async fn edit(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require(ctx, msg, Role::Submitter).await.or_send().await?;
+    let id = args.rest().trim().to_string();
+    let game = Executor::new(ctx, msg)
+        .try_get(|s| s.own_game(&id, &msg.author))
+        .await
         .or_send()
         .await?;
-    let clue5: String = args.single()
-        .or_else(|_| {
-            let m = format!("I got `clue1={}`, `clue2={}`, `clue3={}` and `clue4={}`, but couldn't get clue5", &clue1, &clue2, &clue3, &clue4);
-            Err(ResponseErr::new(ctx, msg, Error::ArgError(m)))
+    let wizard = Wizard::new(ctx, msg);
+    let edited = wizard.review(game.game.clone())
+        .await
+        .map_err(|e| ResponseErr::new(ctx, msg, e))
+        .or_send()
+        .await?;
+    Executor::new(ctx, msg)
+        .try_write(|s| {
+            s.update_game(Game { game: edited, ..game }, &msg.author)?;
+            Ok(ResponseOk::new(ctx, msg)
+                .with_content(format!("Saved your changes to `{}`", id)))
+        })
+        .await
+        .send()
+        .await
+}
+
+/// Deletes one of your games for good
+///
+/// Eg.
+///
+///     !delete 17b2c9a41e0-3f2a
+///
+#[command]
+#[only_in("dm")]
+async fn delete(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require(ctx, msg, Role::Submitter).await.or_send().await?;
+    let id = args.rest().trim().to_string();
+    Executor::new(ctx, msg)
+        .try_write(|s| {
+            let game = s.delete_game(&id, &msg.author)?;
+            Ok(ResponseOk::new(ctx, msg)
+                .with_content(format!("Deleted `{}` ({})", game.id, game.game.playable().summary())))
         })
+        .await
+        .send()
+        .await
+}
+
+/// Hands a game from before Nancy kept track of who submitted what to whoever did, so they can
+/// edit or delete it. Only whoever runs Nancy can do this.
+///
+/// Eg.
+///
+///     !claim ninja-turtles
+///     @mattswoon
+///
+#[command]
+#[only_in("dm")]
+async fn claim(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require_owner(ctx, msg).await.or_send().await?;
+    let mut args = args;
+    let id = args.single::<String>()
+        .map(|id| id.trim().to_string())
+        .map_err(|_| ResponseErr::new(ctx, msg, Error::ArgError("Couldn't get the game's id".to_string())))
+        .or_send()
+        .await?;
+    let user_id = args.single::<String>()
+        .ok()
+        .and_then(|u| u.trim().parse::<UserId>().ok())
+        .ok_or_else(|| ResponseErr::new(ctx, msg, Error::ArgError("Couldn't tell who it belongs to, mention them or give their id".to_string())))
         .or_send()
         .await?;
-    let answer: String = args.single()
+    Executor::new(ctx, msg)
+        .try_write(|s| {
+            let game = s.claim_game(&id, user_id)?;
+            Ok(ResponseOk::new(ctx, msg)
+                .with_content(format!("`{}` ({}) now belongs to <@{}>", game.id, game.game.playable().summary(), user_id)))
+        })
+        .await
+        .send()
+        .await
+}
+
+/// Adds a connecting wall, expects twenty arguments (separated by a newline - shift+enter): the four clues in
+/// each group followed by that group's connection. The clues get shuffled when the wall is played, and no clue can
+/// be on the wall twice.
+///
+/// Eg.
+///
+///     !add-wall
+///     mercury
+///     venus
+///     mars
+///     saturn
+///     planets
+///     ...
+///
+#[command]
+#[only_in("dm")]
+#[aliases("add-wall", "add_wall", "addwall", "wall")]
+async fn add_text_wall_game(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require(ctx, msg, Role::Submitter).await.or_send().await?;
+    let mut args = args;
+    let lines = args.iter::<String>()
+        .filter_map(|a| a.ok())
+        .map(|a| a.trim().to_string())
+        .filter(|a| !a.is_empty())
+        .collect::<Vec<String>>();
+    let groups = Some(lines)
+        .filter(|l| l.len() == GROUPS * (GROUP_SIZE + 1))
+        .ok_or_else(|| {
+            let m = format!("A wall needs {} lines, {} clues and then the connection for each of the {} groups", GROUPS * (GROUP_SIZE + 1), GROUP_SIZE, GROUPS);
+            ResponseErr::new(ctx, msg, Error::ArgError(m))
+        })
+        .or_send()
+        .await?
+        .chunks(GROUP_SIZE + 1)
+        .map(|g| WallGroup {
+            clues: g[..GROUP_SIZE].to_vec(),
+            connection: g[GROUP_SIZE].clone(),
+            accepted: vec![],
+        })
+        .collect();
+    let game = Game::new(&msg.author, GameType::Wall(WallGame::Text(TextWall { groups })));
+    Executor::new(ctx, msg)
+        .try_write(|s| {
+            let game_str = format!("```\n{}\n```", &game);
+            s.add_game(game)?;
+            Ok(ResponseOk::new(ctx, msg)
+                .with_content(format!("Added game:\n{}", game_str)))
+        })
+        .await
+        .send()
+        .await
+}
+
+/// Adds a missing vowels game, expects the category and then one phrase per line (separated by a newline -
+/// shift+enter). Just write the phrases normally, the vowels are taken out and the spaces moved when it's played.
+///
+/// Eg.
+///
+///     !add-vowels
+///     Doctor Who companions
+///     Rose Tyler
+///     Martha Jones
+///     Donna Noble
+///     Amy Pond
+///
+#[command]
+#[only_in("dm")]
+#[aliases("add-vowels", "add_vowels", "addvowels", "vowels", "missing-vowels")]
+async fn add_missing_vowels_game(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require(ctx, msg, Role::Submitter).await.or_send().await?;
+    let mut args = args;
+    let category: String = args.single()
         .or_else(|_| {
-            let m = format!("I got the clues `clue1={}`, `clue2={}`, `clue3={}`, `clue4={}` and `clue5={}`, but couldn't get the answer", &clue1, &clue2, &clue3, &clue4, &clue5);
+            let m = "Couldn't get the category, try again".to_string();
             Err(ResponseErr::new(ctx, msg, Error::ArgError(m)))
         })
         .or_send()
         .await?;
-    let text_seq_game = TextSequence { clue1, clue2, clue3, clue4, clue5, answer };
-    let submitted_by = msg.author.name.clone();
-    let game = Game { submitted_by, game: GameType::Sequence(SequenceGame::Text(text_seq_game)) };
+    let phrases = args.iter::<String>()
+        .filter_map(|a| a.ok())
+        .map(|a| a.trim().to_string())
+        .filter(|a| !a.is_empty())
+        .collect();
+    let game = Game::new(&msg.author, GameType::MissingVowels(MissingVowelsGame::Text(TextMissingVowels { category, phrases })));
     Executor::new(ctx, msg)
-        .write(|s| {
+        .try_write(|s| {
             let game_str = format!("```\n{}\n```", &game);
-            s.add_game(game);
-            ResponseOk::new(ctx, msg)
-                .with_content(format!("Added game:\n{}", game_str))
+            s.add_game(game)?;
+            Ok(ResponseOk::new(ctx, msg)
+                .with_content(format!("Added game:\n{}", game_str)))
         })
         .await
         .send()
         .await
 }
 
+/// Queues a game with `queue` and formats the first clue, or the whole wall
+fn start_round<F>(s: &mut State, key: &SessionKey, queue: F) -> Result<String, Error>
+where
+    F: FnOnce(&mut State, &SessionKey) -> Result<(), Error>
+{
+    s.check_channel(key)?;
+    let clue = queue(s, key)
+        .and_then(|()| s.next_clue(key))?;
+    let deadline = s.wall_deadline(key);
+    let submitted_by = s.submitter(key).ok_or(Error::NoGamePlaying)?;
+    let (game_type, intro) = s.playing(key).ok_or(Error::NoGamePlaying)
+        .map(|p| {
+            let game = p.game.game.playable();
+            let intro = match deadline {
+                Some(d) => format!("{}, you've got {} seconds", game.intro(), d.saturating_sub(unix_now())),
+                None => game.intro().to_string(),
+            };
+            (game.prompt(), intro)
+        })?;
+    Ok(format!(
+r#"Submitted by: {}
+
+{}
+
+{}
+
+>>> {}"#, submitted_by, game_type, intro, clue))
+}
+
+/// Freezes the wall being played in this channel once its time is up, if it has a timer
+async fn start_wall_timer(ctx: &Context, key: SessionKey) {
+    let deadline = ctx.data
+        .read()
+        .await
+        .get::<State>()
+        .and_then(|s| s.wall_deadline(&key));
+    let deadline = match deadline {
+        Some(d) => d,
+        None => return,
+    };
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(deadline.saturating_sub(unix_now()))).await;
+        let frozen = ctx.data
+            .write()
+            .await
+            .get_mut::<State>()
+            .and_then(|s| s.wall_time_up(&key, deadline));
+        if let Some(wall) = frozen {
+            let content = format!("Time's up! Now name the connections with `!guess`\n\n>>> {}", wall);
+            if let Err(e) = key.channel_id.say(&ctx.http, content).await {
+                log::warn!("Couldn't post the frozen wall: {}", e);
+            }
+        }
+    });
+}
+
 /// Pulls a game out of the queue and starts a round with the first clue. To get the next clue
 /// use `!next-clue`, or if you think you know the answer, use `!reveal` to show all the clues
 /// and the answer (hidden with spoiler tags).
@@ -240,103 +699,1316 @@ async fn add_text_sequence_game(ctx: &Context, msg: &Message, args: Args) -> Com
 #[aliases("play")]
 #[only_in("guild")]
 async fn play(ctx: &Context, msg: &Message) -> CommandResult {
-    Executor::new(ctx, msg)
+    require(ctx, msg, Role::Quizmaster).await.or_send().await?;
+    begin_round(ctx, msg, State::queue_game).await
+}
+
+/// Starts a round in this channel with the game `queue` picks, and everything that goes with it
+async fn begin_round<F>(ctx: &Context, msg: &Message, queue: F) -> CommandResult
+where
+    F: Send + FnOnce(&mut State, &SessionKey) -> Result<(), Error>
+{
+    let key = SessionKey::from(msg);
+    let response = Executor::new(ctx, msg)
         .try_write(|s| {
-            let clue = s.queue_game()
-                .and_then(|()| s.next_clue())?;
-            let (game_type, submitted_by) = s.playing.as_ref().ok_or(Error::NoGamePlaying)
-                .map(|p| {
-                    let game_type = match p.game.game {
-                        GameType::Link(_) => "Guess what connects the four clues".to_string(),
-                        GameType::Sequence(_) => "Guess the fifth element in the sequence (and name the pattern)".to_string(),
-                    };
-                    let submitted_by = p.game.submitted_by.clone();
-                    (game_type, submitted_by)
-                })?;
+            let content = start_round(s, &key, queue)?;
             Ok(ResponseOk::new(ctx, msg)
-               .with_content(format!(
-r#"Submitted by: @{}
+               .with_content(content)
+               .with_files(s.images(&key)))
+        })
+        .await
+        .or_send()
+        .await?;
+    let posted = show_round(ctx, &key, response).await?;
+    start_wall_timer(ctx, key).await;
+    start_clue_timer(ctx, key).await;
+    if let Some(posted) = posted {
+        add_controls(ctx, key, posted).await;
+    }
+    Ok(())
+}
 
-{}
+/// Picks the rounds restored from the last run back up, so their timers and controls carry on
+/// where they left off. Walls whose time ran out while we were away freeze straight away.
+async fn resume_rounds(ctx: &Context) {
+    let rounds = ctx.data
+        .read()
+        .await
+        .get::<State>()
+        .map(|s| s.rounds_going()
+             .into_iter()
+             .map(|key| (key, s.round_message(&key)))
+             .collect::<Vec<_>>())
+        .unwrap_or_default();
+    for (key, message_id) in rounds {
+        start_wall_timer(ctx, key).await;
+        start_clue_timer(ctx, key).await;
+        let posted = match message_id {
+            Some(id) => key.channel_id.message(ctx, id).await,
+            None => continue,
+        };
+        match posted {
+            Ok(posted) => add_controls(ctx, key, posted).await,
+            Err(e) => log::warn!("Couldn't find the message for the round in {}: {}", key.channel_id, e),
+        }
+    }
+}
 
-The first clue is
+/// Joins a team for a match in this channel, making the team if there's still room for it.
+/// Once there are two teams a quizmaster can `!start-match`.
+///
+/// Eg.
+///
+///     !team The Wordsmiths
+///
+#[command]
+#[only_in("guild")]
+async fn team(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require(ctx, msg, Role::Player).await.or_send().await?;
+    let name = args.rest().trim().to_string();
+    if name.is_empty() {
+        return ResponseErr::new(ctx, msg, Error::ArgError("Give the name of the team to join".to_string()))
+            .send()
+            .await;
+    }
+    Executor::new(ctx, msg)
+        .try_write(|s| {
+            let key = SessionKey::from(msg);
+            s.check_channel(&key)?;
+            let team = s.join_team(&key, &name, msg.author.id)?;
+            Ok(ResponseOk::new(ctx, msg)
+               .with_content(format!("{} is in {}", msg.author.name, team)))
+        })
+        .await
+        .send()
+        .await
+}
 
->>> {}"#, submitted_by, game_type, clue)))
+/// Shows who's in each team and the match scores
+#[command]
+#[only_in("guild")]
+async fn teams(ctx: &Context, msg: &Message) -> CommandResult {
+    require(ctx, msg, Role::Player).await.or_send().await?;
+    Executor::new(ctx, msg)
+        .try_get(|s| {
+            let m = s.team_match(&SessionKey::from(msg)).ok_or(Error::NoMatch)?;
+            let members = m.teams
+                .iter()
+                .map(|t| format!("{}: {}", t.name, t.members.iter().map(|u| format!("<@{}>", u)).collect::<Vec<String>>().join(", ")))
+                .collect::<Vec<String>>()
+                .join("\n");
+            Ok(ResponseOk::new(ctx, msg)
+               .with_content(format!("{}\n\n{}", members, m.scoreboard())))
         })
         .await
         .send()
         .await
 }
 
-/// Gets the next clue in the game. If you think you know the answer, use `!reveal` to reveal
-/// all the clues and the answer (hidden by a spoiler tag)
+/// Starts the match in this channel, the first team to join picks first
 #[command]
-#[aliases("next-clue", "next")]
+#[aliases("start-match")]
 #[only_in("guild")]
-async fn next_clue(ctx: &Context, msg: &Message) -> CommandResult {
+async fn start_match(ctx: &Context, msg: &Message) -> CommandResult {
+    require(ctx, msg, Role::Quizmaster).await.or_send().await?;
     Executor::new(ctx, msg)
         .try_write(|s| {
-            let (game_type, submitted_by) = s.playing.as_ref().ok_or(Error::NoGamePlaying)
-                .map(|p| {
-                    let game_type = match p.game.game {
-                        GameType::Link(_) => "Guess what connects the four clues".to_string(),
-                        GameType::Sequence(_) => "Guess the next element in the sequence (and name the pattern)".to_string(),
-                    };
-                    let submitted_by = p.game.submitted_by.clone();
-                    (game_type, submitted_by)
-                })?;
-            let clue = s.next_clue()?;
+            let key = SessionKey::from(msg);
+            s.check_channel(&key)?;
+            let m = s.start_match(&key)?;
             Ok(ResponseOk::new(ctx, msg)
-               .with_content(format!(
-r#"Submitted by: @{}
-
-{}
-
-The clues so far are
-
->>> {}"# , submitted_by, game_type, clue)))
+               .with_content(format!("Let the match begin! {} vs {}\n\n{}", m.teams[0].name, m.teams[1].name, m.scoreboard())))
         })
         .await
         .send()
         .await
 }
 
-/// Gets all the clues as well as the answer (hidden by a spoiler tag)
+/// Ends the match in this channel and posts the final scores
 #[command]
+#[aliases("end-match")]
 #[only_in("guild")]
-async fn reveal(ctx: &Context, msg: &Message) -> CommandResult {
+async fn end_match(ctx: &Context, msg: &Message) -> CommandResult {
+    require(ctx, msg, Role::Quizmaster).await.or_send().await?;
     Executor::new(ctx, msg)
         .try_write(|s| {
-            let answer = s.reveal()?;
+            let key = SessionKey::from(msg);
+            s.check_channel(&key)?;
+            let m = s.end_match(&key)?;
+            let result = match m.teams.as_slice() {
+                [a, b] if a.score > b.score => format!("{} win!", a.name),
+                [a, b] if b.score > a.score => format!("{} win!", b.name),
+                [_, _] => "It's a draw!".to_string(),
+                _ => "That's the end of the match".to_string(),
+            };
             Ok(ResponseOk::new(ctx, msg)
-               .with_content(format!("{}", answer)))
+               .with_content(format!("{}\n\n{}", result, m.scoreboard())))
         })
         .await
         .send()
         .await
 }
 
-#[help]
-async fn my_help(
-    context: &Context,
-    msg: &Message,
-    args: Args,
-    help_options: &'static HelpOptions,
-    groups: &[&'static CommandGroup],
-    owners: HashSet<UserId>
-) -> CommandResult {
-    let _ = plain(context, msg, args, &help_options, groups, owners).await;
-    Ok(())
+/// Picks the next game in a match, for the team on turn. Ask for a `link` or a `sequence`, or
+/// leave it out for either.
+///
+/// Eg.
+///
+///     !pick sequence
+///
+#[command]
+#[only_in("guild")]
+async fn pick(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require(ctx, msg, Role::Player).await.or_send().await?;
+    let kind = Some(args.rest().trim().to_lowercase()).filter(|k| !k.is_empty());
+    let user_id = msg.author.id;
+    begin_round(ctx, msg, move |s, key| s.pick_game(key, user_id, kind.as_deref())).await
+}
+
+/// Gives up on the round in this channel without revealing the answer, so the game can be
+/// played again another time
+#[command]
+#[only_in("guild")]
+async fn abandon(ctx: &Context, msg: &Message) -> CommandResult {
+    require(ctx, msg, Role::Quizmaster).await.or_send().await?;
+    Executor::new(ctx, msg)
+        .try_write(|s| {
+            let key = SessionKey::from(msg);
+            s.check_channel(&key)?;
+            let game = s.abandon(&key)?;
+            let _ = s.put_back(&game).or_log();
+            Ok(ResponseOk::new(ctx, msg)
+               .with_content("Round abandoned, that game can come up again another time".to_string()))
+        })
+        .await
+        .send()
+        .await
+}
+
+/// Gives up on the round in this channel without revealing the answer and starts another one,
+/// the skipped game can come up again another time
+#[command]
+#[only_in("guild")]
+async fn skip(ctx: &Context, msg: &Message) -> CommandResult {
+    require(ctx, msg, Role::Quizmaster).await.or_send().await?;
+    let skipped = Executor::new(ctx, msg)
+        .try_write_and_get(|s| {
+            let key = SessionKey::from(msg);
+            s.check_channel(&key)?;
+            s.abandon(&key)
+        })
+        .await
+        .or_send()
+        .await?;
+    // Only put it back once the next round has started, or it could be picked again
+    let started = begin_round(ctx, msg, State::queue_game).await;
+    let _ = Executor::new(ctx, msg)
+        .write_and_get(|s| s.put_back(&skipped).or_log())
+        .await;
+    started
+}
+
+/// Posts the message showing the round (or edits it, if the response says to) and keeps track
+/// of it, so the clues that follow and the answer can go in the same message
+async fn show_round(ctx: &Context, key: &SessionKey, response: ResponseOk<'_>) -> serenity::Result<Option<Message>> {
+    let posted = response.post().await?;
+    if let Some(posted) = &posted {
+        if let Some(s) = ctx.data.write().await.get_mut::<State>() {
+            s.set_round_message(key, posted.id);
+        }
+    }
+    Ok(posted)
+}
+
+/// Starts moving the round along by itself, if the guild has a timer for this type of game
+async fn start_clue_timer(ctx: &Context, key: SessionKey) {
+    let timer = ctx.data
+        .read()
+        .await
+        .get::<State>()
+        .and_then(|s| {
+            let playing = s.playing(&key)?;
+            let seconds = s.guild(key.guild_id).clue_timers.seconds(&playing.game.game)?;
+            Some((playing.game.id.clone(), seconds, playing.clues_shown()))
+        });
+    let (game_id, seconds, shown) = match timer {
+        Some(t) => t,
+        None => return,
+    };
+    let task = tokio::spawn(run_clue_timer(ctx.clone(), key, game_id, seconds, shown));
+    if let Some(s) = ctx.data.write().await.get_mut::<State>() {
+        s.set_timer(&key, task);
+    }
+}
+
+/// Shows the next clue every `seconds` until the last one, then counts down to revealing the
+/// answer. If someone shows a clue themselves in the meantime that clue gets the full time too.
+/// In a match a steal gets the same time again before the answer's revealed.
+async fn run_clue_timer(ctx: Context, key: SessionKey, game_id: String, seconds: u64, shown: usize) {
+    let mut shown = shown;
+    let mut counting_down = false;
+    loop {
+        tokio::time::sleep(Duration::from_secs(seconds)).await;
+        let tick = match ctx.data.write().await.get_mut::<State>() {
+            Some(s) => {
+                let now_shown = match s.playing(&key) {
+                    Some(p) if p.game.id == game_id && !matches!(p.state, GameState::Answered) => p.clues_shown(),
+                    _ => return,
+                };
+                let content = if now_shown != shown {
+                    None
+                } else if counting_down {
+                    Some(s.time_up(&key))
+                } else {
+                    Some(show_next_clue(s, &key))
+                };
+                shown = s.playing(&key).map(PlayingGame::clues_shown).unwrap_or_default();
+                let last = s.playing(&key).map(|p| matches!(p.state, GameState::NoCluesLeft)).unwrap_or(false);
+                let revealed = s.playing(&key).map(|p| matches!(p.state, GameState::Answered)).unwrap_or(true);
+                (content, s.images(&key), last, revealed)
+            },
+            None => return,
+        };
+        let (content, files, last, revealed) = tick;
+        match content {
+            Some(Ok(content)) => if let Err(e) = update_round(&ctx, &key, content, files).await {
+                log::warn!("Couldn't show the timed clue: {}", e);
+            },
+            Some(Err(e)) => {
+                log::warn!("Stopping the clue timer: {}", e);
+                return;
+            },
+            None => (),
+        }
+        if revealed {
+            return;
+        }
+        if last && !counting_down {
+            counting_down = true;
+            let countdown = format!("That's the last clue, the answer's coming in {} seconds ⏳", seconds);
+            if let Err(e) = key.channel_id.say(&ctx, countdown).await {
+                log::warn!("Couldn't post the countdown: {}", e);
+            }
+        }
+    }
+}
+
+/// Shows `content` in the round's message, for when the round moves along without anyone
+/// sending a command
+async fn update_round(ctx: &Context, key: &SessionKey, content: String, files: Vec<PathBuf>) -> CommandResult {
+    let round_message = ctx.data
+        .read()
+        .await
+        .get::<State>()
+        .and_then(|s| s.round_message(key));
+    let round_message = match round_message {
+        Some(id) => key.channel_id.message(ctx, id).await.ok(),
+        None => None,
+    };
+    match round_message {
+        Some(m) => {
+            let response = ResponseOk::new(ctx, &m)
+                .with_content(content)
+                .with_files(files)
+                .with_edit(Some(m.id));
+            show_round(ctx, key, response).await?;
+        },
+        None => {
+            let posted = key.channel_id
+                .send_message(ctx, |m| m.content(content).add_files(&files))
+                .await?;
+            if let Some(s) = ctx.data.write().await.get_mut::<State>() {
+                s.set_round_message(key, posted.id);
+            }
+        },
+    }
+    Ok(())
+}
+
+/// Stops the round in this channel moving along by itself, it carries on with `!next-clue`
+/// and `!reveal` as usual
+#[command]
+#[aliases("stop-timer")]
+#[only_in("guild")]
+async fn stop_timer(ctx: &Context, msg: &Message) -> CommandResult {
+    require(ctx, msg, Role::Quizmaster).await.or_send().await?;
+    Executor::new(ctx, msg)
+        .try_write(|s| {
+            let key = SessionKey::from(msg);
+            s.check_channel(&key)?;
+            let react = if s.stop_timer(&key) { '👍' } else { '🤷' };
+            Ok(ResponseOk::new(ctx, msg)
+               .with_react(react))
+        })
+        .await
+        .send()
+        .await
+}
+
+const NEXT_CLUE: char = '⏭';
+const REVEAL: char = '🔍';
+
+/// How long the reactions on a round keep working after the last time anyone used them
+const CONTROLS_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// Reacting to the round's message with ⏭ shows the next clue and 🔍 reveals the answer, the
+/// same as `!next-clue` and `!reveal`. Each reaction is taken off again so it can be used more
+/// than once, and we stop listening once the round is over or nobody has touched it for a while.
+async fn add_controls(ctx: &Context, key: SessionKey, posted: Message) {
+    for control in [NEXT_CLUE, REVEAL].iter() {
+        if let Err(e) = posted.react(ctx, *control).await {
+            log::warn!("Couldn't add the {} control: {}", control, e);
+            return;
+        }
+    }
+    let game_id = match Executor::new(ctx, &posted).get(|s| s.playing(&key).map(|p| p.game.id.clone())).await {
+        Ok(Some(id)) => id,
+        _ => return,
+    };
+    let bot_id = ctx.cache.current_user_id().await;
+    // The collector's own timeout is for its whole life, so how long it's been idle is timed
+    // here instead. It stops when `reactions` is dropped.
+    let mut reactions = posted.await_reactions(ctx)
+        .removed(false)
+        .filter(move |r| r.user_id != Some(bot_id))
+        .await;
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        while let Ok(Some(action)) = tokio::time::timeout(CONTROLS_TIMEOUT, reactions.next()).await {
+            let reaction = action.as_inner_ref();
+            let still_playing = ctx.data
+                .read()
+                .await
+                .get::<State>()
+                .and_then(|s| s.playing(&key))
+                .map(|p| p.game.id == game_id && !matches!(p.state, GameState::Answered))
+                .unwrap_or(false);
+            if !still_playing {
+                break;
+            }
+            let control = match &reaction.emoji {
+                ReactionType::Unicode(e) => e.chars().next(),
+                _ => None,
+            };
+            let user_id = match (reaction.user_id, control) {
+                (Some(u), Some(c)) if c == NEXT_CLUE || c == REVEAL => u,
+                _ => continue,
+            };
+            let _ = reaction.delete(&ctx).await;
+            let role = if control == Some(NEXT_CLUE) {
+                clue_role(&ctx, &key).await
+            } else {
+                Role::Quizmaster
+            };
+            if !is_allowed(&ctx, key.guild_id, user_id, role).await {
+                continue;
+            }
+            let response = ctx.data
+                .write()
+                .await
+                .get_mut::<State>()
+                .ok_or(Error::NoState)
+                .and_then(|s| {
+                    let content = if control == Some(NEXT_CLUE) {
+                        s.check_turn(&key, user_id)?;
+                        show_next_clue(s, &key)?
+                    } else {
+                        s.stop_timer(&key);
+                        s.reveal(&key)?
+                    };
+                    Ok(ResponseOk::new(&ctx, &posted)
+                       .with_content(content)
+                       .with_files(s.images(&key))
+                       .with_edit(s.round_message(&key)))
+                });
+            match response {
+                Ok(r) => if let Err(e) = show_round(&ctx, &key, r).await {
+                    log::warn!("Couldn't respond to a control: {}", e);
+                },
+                Err(e) => {
+                    let _ = ResponseErr::new(&ctx, &posted, e).send().await;
+                },
+            }
+        }
+    });
+}
+
+/// Starts a round with the game that was played longest ago, for when every game has been
+/// played and nobody has added any new ones
+#[command]
+#[only_in("guild")]
+async fn recycle(ctx: &Context, msg: &Message) -> CommandResult {
+    require(ctx, msg, Role::Admin).await.or_send().await?;
+    begin_round(ctx, msg, |s, key| s.queue_game_with(key, ExhaustedPolicy::Recycle)).await
+}
+
+/// Gets the next clue in the game. If you think you know the answer, use `!reveal` to reveal
+/// all the clues and the answer (hidden by a spoiler tag). In missing vowels this skips the
+/// phrase that's up.
+#[command]
+#[aliases("next-clue", "next")]
+#[only_in("guild")]
+async fn next_clue(ctx: &Context, msg: &Message) -> CommandResult {
+    let key = SessionKey::from(msg);
+    require(ctx, msg, clue_role(ctx, &key).await).await.or_send().await?;
+    let response = Executor::new(ctx, msg)
+        .try_write(|s| {
+            s.check_turn(&key, msg.author.id)?;
+            let content = show_next_clue(s, &key)?;
+            Ok(ResponseOk::new(ctx, msg)
+               .with_react('👍')
+               .with_files(s.images(&key))
+               .with_content(content)
+               .with_edit(s.round_message(&key)))
+        })
+        .await
+        .or_send()
+        .await?;
+    show_round(ctx, &key, response).await?;
+    Ok(())
+}
+
+/// Who can ask for the next clue. In a match it's up to the team on turn (see
+/// `State::check_turn`), anywhere else it's the quizmasters.
+async fn clue_role(ctx: &Context, key: &SessionKey) -> Role {
+    let in_match = ctx.data
+        .read()
+        .await
+        .get::<State>()
+        .and_then(|s| s.team_match(key))
+        .map(|m| m.started)
+        .unwrap_or(false);
+    if in_match {
+        Role::Player
+    } else {
+        Role::Quizmaster
+    }
+}
+
+/// Moves on to the next clue and formats the clues so far
+fn show_next_clue(s: &mut State, key: &SessionKey) -> Result<String, Error> {
+    s.check_channel(key)?;
+    let submitted_by = s.submitter(key).ok_or(Error::NoGamePlaying)?;
+    let (game_type, heading) = s.playing(key).ok_or(Error::NoGamePlaying)
+        .map(|p| {
+            let game = p.game.game.playable();
+            (game.prompt(), game.clues_heading())
+        })?;
+    let clue = s.next_clue(key)?;
+    let heading = heading
+        .map(|h| format!("{}\n\n", h))
+        .unwrap_or_default();
+    Ok(format!(
+r#"Submitted by: {}
+
+{}
+
+{}>>> {}"# , submitted_by, game_type, heading, clue))
+}
+
+/// Gets all the clues as well as the answer (hidden by a spoiler tag)
+#[command]
+#[only_in("guild")]
+async fn reveal(ctx: &Context, msg: &Message) -> CommandResult {
+    require(ctx, msg, Role::Quizmaster).await.or_send().await?;
+    let key = SessionKey::from(msg);
+    let response = Executor::new(ctx, msg)
+        .try_write(|s| {
+            s.check_channel(&key)?;
+            s.stop_timer(&key);
+            let answer = s.reveal(&key)?;
+            Ok(ResponseOk::new(ctx, msg)
+               .with_react('👍')
+               .with_content(answer)
+               .with_files(s.images(&key))
+               .with_edit(s.round_message(&key)))
+        })
+        .await
+        .or_send()
+        .await?;
+    show_round(ctx, &key, response).await?;
+    Ok(())
+}
+
+/// Puts forward four clues on the wall as a group, one per line or separated by commas. Once two
+/// groups are found you've only got three lives, and after that (or when time's up) the wall
+/// freezes and it's on to naming the connections with `!guess`.
+///
+/// Eg.
+///
+///     !group mercury, venus, mars, saturn
+///
+#[command]
+#[only_in("guild")]
+async fn group(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require(ctx, msg, Role::Player).await.or_send().await?;
+    let clues = args.rest()
+        .split(|c| c == '\n' || c == ',')
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect::<Vec<String>>();
+    Executor::new(ctx, msg)
+        .try_write(|s| {
+            let key = SessionKey::from(msg);
+            s.check_channel(&key)?;
+            let (result, wall) = s.submit_group(&key, msg.author.id, &msg.author.name, &clues)?;
+            Ok(match result {
+                GroupResult::Solved => ResponseOk::new(ctx, msg)
+                    .with_react('✅')
+                    .with_content(format!("That's the wall solved! Now name the connections with `!guess`\n\n>>> {}", wall)),
+                GroupResult::Found(_) => ResponseOk::new(ctx, msg)
+                    .with_react('✅')
+                    .with_content(format!(">>> {}", wall)),
+                GroupResult::Wrong => ResponseOk::new(ctx, msg)
+                    .with_react('❌')
+                    .with_content(format!(">>> {}", wall)),
+                GroupResult::Frozen => ResponseOk::new(ctx, msg)
+                    .with_react('🧊')
+                    .with_content(format!("The wall's frozen! Now name the connections with `!guess`\n\n>>> {}", wall)),
+            })
+        })
+        .await
+        .send()
+        .await
+}
+
+/// Starts or stops the timer on connecting walls, eg. `!set-wall-timer 150` gives players two
+/// and a half minutes to find the groups and `!set-wall-timer off` lets them take as long as
+/// they like
+#[command]
+#[aliases("set-wall-timer")]
+#[only_in("guild")]
+async fn set_wall_timer(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require(ctx, msg, Role::Admin).await.or_send().await?;
+    let seconds = match args.rest().trim() {
+        "off" => Ok(None),
+        other => other.parse::<u64>()
+            .map(Some)
+            .map_err(|_| ResponseErr::new(ctx, msg, Error::ArgError(format!("`{}` should be a number of seconds or off", other)))),
+    }
+        .or_send()
+        .await?;
+    Executor::new(ctx, msg)
+        .try_write(|s| {
+            let guild_id = msg.guild_id.ok_or(Error::NotInGuild)?;
+            s.update_guild(guild_id, |g| {
+                g.wall_seconds = seconds;
+                Ok(())
+            })?;
+            Ok(ResponseOk::new(ctx, msg)
+               .with_react('👍'))
+        })
+        .await
+        .send()
+        .await
+}
+
+/// Judges a guess, reacting with ✅ and revealing the answer if it's right or ❌ if it's not.
+/// The answer goes in the round's message, same as when it's revealed any other way.
+async fn judge_guess(ctx: &Context, msg: &Message, guess: &str) -> CommandResult {
+    let key = SessionKey::from(msg);
+    let (response, answered) = Executor::new(ctx, msg)
+        .try_write_and_get(|s| {
+            s.check_channel(&key)?;
+            let in_match = s.team_match(&key).map(|m| m.started).unwrap_or(false);
+            let judged = if in_match {
+                Some(s.match_guess(&key, msg.author.id, &msg.author.name, guess)?)
+            } else {
+                None
+            };
+            let solved = match judged {
+                Some(_) => None,
+                None => s.guess(&key, msg.author.id, &msg.author.name, guess)?,
+            };
+            let answered = s.playing(&key).map(|p| matches!(p.state, GameState::Answered)).unwrap_or(false);
+            if answered {
+                s.stop_timer(&key);
+            }
+            let edit = if answered { s.round_message(&key) } else { None };
+            if let Some((right, content)) = judged {
+                return Ok((ResponseOk::new(ctx, msg)
+                    .with_react(if right { '✅' } else { '❌' })
+                    .with_content(content)
+                    .with_files(s.images(&key))
+                    .with_edit(edit), answered));
+            }
+            Ok((match solved {
+                Some(Solved { answer, points }) => ResponseOk::new(ctx, msg)
+                    .with_react('✅')
+                    .with_content(format!("{} got it for {} points!\n\n{}", msg.author.name, points, answer))
+                    .with_files(s.images(&key))
+                    .with_edit(edit),
+                None => ResponseOk::new(ctx, msg)
+                    .with_react('❌'),
+            }, answered))
+        })
+        .await
+        .or_send()
+        .await?;
+    if answered {
+        show_round(ctx, &key, response).await?;
+    } else {
+        response.post().await?;
+    }
+    Ok(())
+}
+
+/// Have a guess at the answer. If you're right the round ends and the answer is revealed, so
+/// you don't need to hide your guess in spoiler tags.
+///
+/// Eg.
+///
+///     !guess ninja turtles
+///
+#[command]
+#[only_in("guild")]
+async fn guess(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require(ctx, msg, Role::Player).await.or_send().await?;
+    let guess = args.rest().to_string();
+    judge_guess(ctx, msg, &guess).await
+}
+
+fn format_leaderboard(title: String, ranked: Vec<(UserId, &PlayerScore)>) -> String {
+    if ranked.is_empty() {
+        return format!("{}\n\nNobody has scored yet", title);
+    }
+    let rows = ranked.iter()
+        .take(10)
+        .enumerate()
+        .map(|(i, (_, p))| format!("{}. {}", i + 1, p))
+        .collect::<Vec<String>>()
+        .join("\n");
+    format!("{}\n\n{}", title, rows)
+}
+
+/// Shows the top ten players this season, or in an earlier season if you give its number
+///
+/// Eg.
+///
+///     !leaderboard 2
+///
+#[command]
+#[only_in("guild")]
+async fn leaderboard(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require(ctx, msg, Role::Player).await.or_send().await?;
+    let mut args = args;
+    let season = args.single::<u32>().ok();
+    Executor::new(ctx, msg)
+        .try_get(|s| {
+            let guild_id = msg.guild_id.ok_or(Error::NotInGuild)?;
+            let scores = s.scores(guild_id);
+            let content = match season {
+                None => format_leaderboard(format!("Season {}", scores.season), scores.leaderboard()),
+                Some(n) if n == scores.season => format_leaderboard(format!("Season {}", n), scores.leaderboard()),
+                Some(n) => scores.past_season(n)
+                    .map(|past| format_leaderboard(format!("Season {} (finished)", n), ranked(&past.players)))
+                    .ok_or_else(|| Error::ArgError(format!("There's no season {}", n)))?,
+            };
+            Ok(ResponseOk::new(ctx, msg)
+               .with_content(content))
+        })
+        .await
+        .send()
+        .await
+}
+
+/// Shows your score this season, or someone else's if you mention them
+#[command]
+#[only_in("guild")]
+async fn score(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require(ctx, msg, Role::Player).await.or_send().await?;
+    let mut args = args;
+    let user_id = args.single::<UserId>().unwrap_or(msg.author.id);
+    Executor::new(ctx, msg)
+        .try_get(|s| {
+            let guild_id = msg.guild_id.ok_or(Error::NotInGuild)?;
+            let scores = s.scores(guild_id);
+            let content = scores.leaderboard()
+                .iter()
+                .enumerate()
+                .find(|(_, (u, _))| *u == user_id)
+                .map(|(i, (_, p))| format!("#{} in season {}: {}", i + 1, scores.season, p))
+                .unwrap_or_else(|| format!("<@{}> hasn't scored this season", user_id));
+            Ok(ResponseOk::new(ctx, msg)
+               .with_content(content))
+        })
+        .await
+        .send()
+        .await
+}
+
+/// Ends the current season, putting everyone back on zero. The old season can still be seen
+/// with `!leaderboard <season>`
+#[command]
+#[aliases("season-reset")]
+#[only_in("guild")]
+async fn season_reset(ctx: &Context, msg: &Message) -> CommandResult {
+    require(ctx, msg, Role::Admin).await.or_send().await?;
+    Executor::new(ctx, msg)
+        .try_write(|s| {
+            let guild_id = msg.guild_id.ok_or(Error::NotInGuild)?;
+            let season = s.reset_season(guild_id)?;
+            let title = format!("That's the end of season {}! Final standings:", season.number);
+            Ok(ResponseOk::new(ctx, msg)
+               .with_content(format_leaderboard(title, ranked(&season.players))))
+        })
+        .await
+        .send()
+        .await
+}
+
+/// Makes this channel the only one games can be played in. You can also mention another
+/// channel after the command to use that one instead.
+#[command]
+#[aliases("set-channel")]
+#[only_in("guild")]
+async fn set_channel(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require(ctx, msg, Role::Admin).await.or_send().await?;
+    let mut args = args;
+    let channel_id = if args.is_empty() {
+        msg.channel_id
+    } else {
+        args.single::<ChannelId>()
+            .or_else(|_| {
+                let m = format!("`{}` isn't a channel, try mentioning it like #games", args.rest());
+                Err(ResponseErr::new(ctx, msg, Error::ArgError(m)))
+            })
+            .or_send()
+            .await?
+    };
+    Executor::new(ctx, msg)
+        .try_write(|s| {
+            let guild_id = msg.guild_id.ok_or(Error::NotInGuild)?;
+            s.update_guild(guild_id, |g| {
+                g.main_channel = Some(channel_id);
+                Ok(())
+            })?;
+            Ok(ResponseOk::new(ctx, msg)
+               .with_content(format!("Games will be played in <#{}> from now on", channel_id)))
+        })
+        .await
+        .send()
+        .await
+}
+
+/// Lets games be played in any channel again
+#[command]
+#[aliases("clear-channel")]
+#[only_in("guild")]
+async fn clear_channel(ctx: &Context, msg: &Message) -> CommandResult {
+    require(ctx, msg, Role::Admin).await.or_send().await?;
+    Executor::new(ctx, msg)
+        .try_write(|s| {
+            let guild_id = msg.guild_id.ok_or(Error::NotInGuild)?;
+            s.update_guild(guild_id, |g| {
+                g.main_channel
+                    .take()
+                    .map(|_| ())
+                    .ok_or(Error::NoMainChannel)
+            })?;
+            Ok(ResponseOk::new(ctx, msg)
+               .with_content("Games can be played in any channel now".to_string()))
+        })
+        .await
+        .send()
+        .await
+}
+
+/// Sets how many typos a guess can have and still count as right (2 to start with)
+#[command]
+#[aliases("set-tolerance")]
+#[only_in("guild")]
+async fn set_tolerance(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require(ctx, msg, Role::Admin).await.or_send().await?;
+    let mut args = args;
+    let tolerance: usize = args.single()
+        .or_else(|_| {
+            let m = format!("`{}` isn't a number", args.rest());
+            Err(ResponseErr::new(ctx, msg, Error::ArgError(m)))
+        })
+        .or_send()
+        .await?;
+    Executor::new(ctx, msg)
+        .try_write(|s| {
+            let guild_id = msg.guild_id.ok_or(Error::NotInGuild)?;
+            s.update_guild(guild_id, |g| {
+                g.guess_tolerance = tolerance;
+                Ok(())
+            })?;
+            Ok(ResponseOk::new(ctx, msg)
+               .with_react('👍'))
+        })
+        .await
+        .send()
+        .await
+}
+
+/// `!capture-spoilers on` makes any `||spoilered||` message in the game channel count as a
+/// guess, `!capture-spoilers off` turns that off again
+#[command]
+#[aliases("capture-spoilers")]
+#[only_in("guild")]
+async fn capture_spoilers(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require(ctx, msg, Role::Admin).await.or_send().await?;
+    let capture = match args.rest().trim() {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        other => Err(ResponseErr::new(ctx, msg, Error::ArgError(format!("`{}` should be on or off", other)))),
+    }
+        .or_send()
+        .await?;
+    Executor::new(ctx, msg)
+        .try_write(|s| {
+            let guild_id = msg.guild_id.ok_or(Error::NotInGuild)?;
+            s.update_guild(guild_id, |g| {
+                g.capture_spoilers = capture;
+                Ok(())
+            })?;
+            Ok(ResponseOk::new(ctx, msg)
+               .with_react('👍'))
+        })
+        .await
+        .send()
+        .await
+}
+
+/// Times rounds of one type of game, showing the next clue by itself if nobody gets it in
+/// that many seconds and revealing the answer that long after the last one. `off` stops timing
+/// them.
+///
+/// Eg.
+///
+///     !set-clue-timer link 45
+///     !set-clue-timer vowels off
+///
+#[command]
+#[aliases("set-clue-timer")]
+#[only_in("guild")]
+async fn set_clue_timer(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require(ctx, msg, Role::Admin).await.or_send().await?;
+    let words = args.rest().split_whitespace().map(String::from).collect::<Vec<String>>();
+    let seconds = match words.get(1).map(String::as_str) {
+        Some("off") => Ok(None),
+        Some(other) => other.parse::<u64>()
+            .ok()
+            .filter(|s| *s > 0)
+            .map(Some)
+            .ok_or_else(|| Error::ArgError(format!("`{}` should be a number of seconds or off", other))),
+        None => Err(Error::ArgError("Expected a game type followed by a number of seconds, eg. `!set-clue-timer link 45`".to_string())),
+    }
+        .map_err(|e| ResponseErr::new(ctx, msg, e))
+        .or_send()
+        .await?;
+    let game_type = words.first().cloned().unwrap_or_default();
+    Executor::new(ctx, msg)
+        .try_write(|s| {
+            let guild_id = msg.guild_id.ok_or(Error::NotInGuild)?;
+            s.update_guild(guild_id, |g| {
+                *g.clue_timers.seconds_mut(&game_type)? = seconds;
+                Ok(())
+            })?;
+            Ok(ResponseOk::new(ctx, msg)
+               .with_react('👍'))
+        })
+        .await
+        .send()
+        .await
+}
+
+/// How many minutes a round can go without anyone doing anything before the answer's revealed
+/// so another one can be played, or `off` to let rounds go on forever
+///
+/// Eg.
+///
+///     !set-idle-timeout 30
+///
+#[command]
+#[aliases("set-idle-timeout")]
+#[only_in("guild")]
+async fn set_idle_timeout(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require(ctx, msg, Role::Admin).await.or_send().await?;
+    let minutes = match args.rest().trim() {
+        "off" => Ok(None),
+        other => other.parse::<u64>()
+            .ok()
+            .filter(|m| *m > 0)
+            .map(Some)
+            .ok_or_else(|| ResponseErr::new(ctx, msg, Error::ArgError(format!("`{}` should be a number of minutes or off", other)))),
+    }
+        .or_send()
+        .await?;
+    Executor::new(ctx, msg)
+        .try_write(|s| {
+            let guild_id = msg.guild_id.ok_or(Error::NotInGuild)?;
+            s.update_guild(guild_id, |g| {
+                g.idle_minutes = minutes;
+                Ok(())
+            })?;
+            Ok(ResponseOk::new(ctx, msg)
+               .with_react('👍'))
+        })
+        .await
+        .send()
+        .await
+}
+
+/// `!mention-submitters on` pings whoever submitted a game when it's played,
+/// `!mention-submitters off` goes back to just showing their name
+#[command]
+#[aliases("mention-submitters")]
+#[only_in("guild")]
+async fn mention_submitters(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require(ctx, msg, Role::Admin).await.or_send().await?;
+    let mention = match args.rest().trim() {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        other => Err(ResponseErr::new(ctx, msg, Error::ArgError(format!("`{}` should be on or off", other)))),
+    }
+        .or_send()
+        .await?;
+    Executor::new(ctx, msg)
+        .try_write(|s| {
+            let guild_id = msg.guild_id.ok_or(Error::NotInGuild)?;
+            s.update_guild(guild_id, |g| {
+                g.mention_submitters = mention;
+                Ok(())
+            })?;
+            Ok(ResponseOk::new(ctx, msg)
+               .with_react('👍'))
+        })
+        .await
+        .send()
+        .await
+}
+
+/// `!set-moderation on` only plays games a moderator has approved, `!set-moderation off` plays
+/// anything that hasn't been rejected
+#[command]
+#[aliases("set-moderation")]
+#[only_in("guild")]
+async fn set_moderation(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require(ctx, msg, Role::Admin).await.or_send().await?;
+    let moderated = match args.rest().trim() {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        other => Err(ResponseErr::new(ctx, msg, Error::ArgError(format!("`{}` should be on or off", other)))),
+    }
+        .or_send()
+        .await?;
+    Executor::new(ctx, msg)
+        .try_write(|s| {
+            let guild_id = msg.guild_id.ok_or(Error::NotInGuild)?;
+            s.update_guild(guild_id, |g| {
+                g.moderated = moderated;
+                Ok(())
+            })?;
+            Ok(ResponseOk::new(ctx, msg)
+               .with_react('👍'))
+        })
+        .await
+        .send()
+        .await
+}
+
+/// DMs you the games waiting to be approved, they'd give away the answers in the channel. The
+/// games are shared by every guild so only whoever runs Nancy can moderate them.
+#[command]
+async fn pending(ctx: &Context, msg: &Message) -> CommandResult {
+    require_owner(ctx, msg).await.or_send().await?;
+    let dm_chan = msg.author.create_dm_channel(ctx).await?;
+    Executor::new(ctx, msg)
+        .try_get(|s| {
+            let games = s.pending_games()?;
+            let content = if games.is_empty() {
+                "Nothing waiting to be approved".to_string()
+            } else {
+                games.iter()
+                    .map(|g| format!("`{}` {} by {} - {}", g.id, g.game.kind(), g.submitted_by, g.game.playable().summary()))
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            };
+            Ok(ResponseOk::new(ctx, msg)
+                .with_dm_channel(&dm_chan)
+                .with_content(content)
+                .with_react('📬'))
+        })
+        .await
+        .send()
+        .await
+}
+
+/// DMs you a game the way it'll look when it's revealed, to check before approving it
+///
+/// Eg.
+///
+///     !preview 17b2c9a41e0-3f2a
+///
+#[command]
+async fn preview(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require_owner(ctx, msg).await.or_send().await?;
+    let id = args.rest().trim().to_string();
+    let dm_chan = msg.author.create_dm_channel(ctx).await?;
+    Executor::new(ctx, msg)
+        .try_get(|s| {
+            let game = s.game(&id)?;
+            Ok(ResponseOk::new(ctx, msg)
+                .with_dm_channel(&dm_chan)
+                .with_content(format!("`{}` ({})\n{}", game.id, game.review.name(), game))
+                .with_react('📬'))
+        })
+        .await
+        .send()
+        .await
+}
+
+/// Lets a game be played in guilds with moderation on, and tells whoever submitted it
+///
+/// Eg.
+///
+///     !approve 17b2c9a41e0-3f2a
+///
+#[command]
+async fn approve(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require_owner(ctx, msg).await.or_send().await?;
+    let id = args.rest().trim().to_string();
+    let game = Executor::new(ctx, msg)
+        .try_write_and_get(|s| s.review_game(&id, Review::Approved))
+        .await
+        .or_send()
+        .await?;
+    tell_submitter(ctx, &game).await;
+    msg.react(ctx, '👍').await?;
+    Ok(())
+}
+
+/// Stops a game being played anywhere, and tells whoever submitted it why so they can fix it
+/// with `!edit`
+///
+/// Eg.
+///
+///     !reject 17b2c9a41e0-3f2a the answer's wrong, it should be Kyoshi
+///
+#[command]
+async fn reject(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require_owner(ctx, msg).await.or_send().await?;
+    let (id, reason) = match args.rest().trim().split_once(char::is_whitespace) {
+        Some((id, reason)) if !reason.trim().is_empty() => Ok((id.to_string(), reason.trim().to_string())),
+        _ => Err(ResponseErr::new(ctx, msg, Error::ArgError("Give the game id and then why it's rejected".to_string()))),
+    }
+        .or_send()
+        .await?;
+    let game = Executor::new(ctx, msg)
+        .try_write_and_get(|s| s.review_game(&id, Review::Rejected(reason)))
+        .await
+        .or_send()
+        .await?;
+    tell_submitter(ctx, &game).await;
+    msg.react(ctx, '👍').await?;
+    Ok(())
+}
+
+/// DMs whoever submitted a game whether it was approved or rejected
+async fn tell_submitter(ctx: &Context, game: &Game) {
+    let user_id = match game.submitter_id {
+        Some(id) => id,
+        None => return,
+    };
+    let about = format!("Your {} game `{}` ({})", game.game.kind(), game.id, game.game.playable().summary());
+    let text = match &game.review {
+        Review::Approved => format!("{} has been approved", about),
+        Review::Rejected(reason) => format!("{} has been rejected: {}\n\nYou can fix it with `!edit {}`", about, reason, game.id),
+        Review::Pending => return,
+    };
+    let sent = match user_id.create_dm_channel(ctx).await {
+        Ok(chan) => chan.say(ctx, text).await.map(|_| ()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = sent {
+        log::warn!("Couldn't tell {} about their game: {}", user_id, e);
+    }
+}
+
+/// Picks the discord role needed for player, submitter, quizmaster or admin commands. Each one
+/// can also do everything the ones before it can. `everyone` lets anyone do it again (or just
+/// Administrators, for admin).
+///
+/// Eg.
+///
+///     !set-role quizmaster @Quizmasters
+///     !set-role player everyone
+///
+#[command]
+#[aliases("set-role")]
+#[only_in("guild")]
+async fn set_role(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require(ctx, msg, Role::Admin).await.or_send().await?;
+    let (role, role_id) = match args.rest().trim().split_once(char::is_whitespace) {
+        Some((role, discord_role)) => role.parse::<Role>()
+            .and_then(|role| match discord_role.trim() {
+                "everyone" => Ok((role, None)),
+                other => other.parse::<RoleId>()
+                    .map(|id| (role, Some(id)))
+                    .map_err(|_| Error::ArgError(format!("`{}` should be a role or everyone", other))),
+            }),
+        None => Err(Error::ArgError("Give player, submitter, quizmaster or admin and then the role".to_string())),
+    }
+        .map_err(|e| ResponseErr::new(ctx, msg, e))
+        .or_send()
+        .await?;
+    Executor::new(ctx, msg)
+        .try_write(|s| {
+            let guild_id = msg.guild_id.ok_or(Error::NotInGuild)?;
+            s.update_guild(guild_id, |g| {
+                g.roles.set(role, role_id);
+                Ok(())
+            })?;
+            Ok(ResponseOk::new(ctx, msg)
+               .with_react('👍'))
+        })
+        .await
+        .send()
+        .await
+}
+
+/// Sets the points for a right answer depending on how many clues were showing, for either
+/// link or sequence games. The last number is used for any clues after that.
+///
+/// Eg.
+///
+///     !set-scoring link 5 3 2 1
+///
+#[command]
+#[aliases("set-scoring")]
+#[only_in("guild")]
+async fn set_scoring(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    require(ctx, msg, Role::Admin).await.or_send().await?;
+    let words = args.rest().split_whitespace().map(String::from).collect::<Vec<String>>();
+    let table = words.iter()
+        .skip(1)
+        .map(|w| w.parse::<u32>())
+        .collect::<Result<Vec<u32>, _>>()
+        .ok()
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| {
+            let m = "Expected a game type followed by the points for each clue, eg. `!set-scoring link 5 3 2 1`".to_string();
+            ResponseErr::new(ctx, msg, Error::ArgError(m))
+        })
+        .or_send()
+        .await?;
+    let game_type = words.first().cloned().unwrap_or_default();
+    Executor::new(ctx, msg)
+        .try_write(|s| {
+            let guild_id = msg.guild_id.ok_or(Error::NotInGuild)?;
+            s.update_guild(guild_id, |g| {
+                *g.scoring.table_mut(&game_type)? = table;
+                Ok(())
+            })?;
+            Ok(ResponseOk::new(ctx, msg)
+               .with_react('👍'))
+        })
+        .await
+        .send()
+        .await
+}
+
+#[help]
+async fn my_help(
+    context: &Context,
+    msg: &Message,
+    args: Args,
+    help_options: &'static HelpOptions,
+    groups: &[&'static CommandGroup],
+    owners: HashSet<UserId>
+) -> CommandResult {
+    let _ = plain(context, msg, args, &help_options, groups, owners).await;
+    Ok(())
 }
 
 
 #[group]
-#[commands(status, add_game, play, add_text_link_game, add_text_sequence_game, next_clue, reveal)]
+#[commands(status, add_game, play, add_text_link_game, add_text_sequence_game, add_text_wall_game, add_missing_vowels_game, new_game, my_games, edit, delete, claim, next_clue, stop_timer, abandon, skip, team, teams, start_match, end_match, pick, guess, group, reveal, leaderboard, score)]
 struct General;
 
+#[group]
+#[commands(recycle, set_channel, clear_channel, set_tolerance, capture_spoilers, set_scoring, set_wall_timer, set_clue_timer, set_idle_timeout, mention_submitters, set_moderation, set_role, pending, preview, approve, reject, season_reset)]
+struct Admin;
+
 struct Handler;
 
-impl EventHandler for Handler {}
+/// How often to look for rounds that have been left idle
+const IDLE_CHECK: Duration = Duration::from_secs(60);
+
+/// Whether the idle check is already going and the restored rounds picked back up, `ready`
+/// happens again every time we reconnect
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Reveals the answer to any round that's been left alone longer than its guild's idle timeout,
+/// so the channel can play again
+async fn check_idle_rounds(ctx: Context) {
+    loop {
+        tokio::time::sleep(IDLE_CHECK).await;
+        let revealed = match ctx.data.write().await.get_mut::<State>() {
+            Some(s) => s.idle_rounds(unix_now())
+                .into_iter()
+                .filter_map(|key| {
+                    s.stop_timer(&key);
+                    let answer = s.time_up(&key).or_log().ok()?;
+                    let answered = s.playing(&key).map(|p| matches!(p.state, GameState::Answered)).unwrap_or(true);
+                    let minutes = s.guild(key.guild_id).idle_minutes.unwrap_or_default();
+                    Some((key, answer, s.images(&key), minutes, answered))
+                })
+                .collect::<Vec<_>>(),
+            None => continue,
+        };
+        for (key, answer, files, minutes, answered) in revealed {
+            if let Err(e) = update_round(&ctx, &key, answer, files).await {
+                log::warn!("Couldn't reveal an idle round: {}", e);
+            }
+            // A steal gets its own go before the answer's revealed
+            if !answered {
+                continue;
+            }
+            let notice = format!("Nobody's done anything with this round for {} minutes, so that's the answer ⬆️ `!play` to start another", minutes);
+            if let Err(e) = key.channel_id.say(&ctx, notice).await {
+                log::warn!("Couldn't post the idle notice: {}", e);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl EventHandler for Handler {
+    async fn ready(&self, ctx: Context, _ready: Ready) {
+        if !STARTED.swap(true, Ordering::SeqCst) {
+            resume_rounds(&ctx).await;
+            tokio::spawn(check_idle_rounds(ctx));
+        }
+    }
+
+    /// Spoilered messages in a channel with a round going count as guesses, if the guild has
+    /// turned that on
+    async fn message(&self, ctx: Context, msg: Message) {
+        if msg.author.bot || msg.content.starts_with('!') {
+            return;
+        }
+        let guess = match spoiler(&msg.content) {
+            Some(g) => g.to_string(),
+            None => return,
+        };
+        let key = SessionKey::from(&msg);
+        let capture = Executor::new(&ctx, &msg)
+            .get(|s| {
+                s.guild(key.guild_id).capture_spoilers
+                    && s.check_channel(&key).is_ok()
+                    && s.playing(&key).map(|p| p.is_guessable()).unwrap_or(false)
+            })
+            .await
+            .unwrap_or(false);
+        if capture && require(&ctx, &msg, Role::Player).await.is_ok() {
+            let _ = judge_guess(&ctx, &msg, &guess).await;
+        }
+    }
+}
 
 #[tokio::main]
 async fn main() {
@@ -349,21 +2021,65 @@ async fn main() {
     let token = std::env::var("DISCORD_TOKEN")
         .expect("Couldn't get discord token");
 
-    let games_dir = std::env::var("NANCY_GAMES")
-        .unwrap_or("./games/".to_string());
+    let exhausted_policy = std::env::var("NANCY_WHEN_ALL_PLAYED")
+        .unwrap_or("recycle".to_string())
+        .parse::<ExhaustedPolicy>()
+        .unwrap_or_else(|e| {
+            eprintln!("NANCY_WHEN_ALL_PLAYED: {}", e);
+            std::process::exit(1);
+        });
 
-    let games = std::fs::read_dir(games_dir)
+    let games_dir = std::env::var("NANCY_GAMES");
+    let store: Result<Box<dyn GameStore>, Error> = match std::env::var("NANCY_DB") {
+        Ok(db) => SqliteStore::open(db)
+            .and_then(|mut store| {
+                if let Ok(dir) = &games_dir {
+                    store.import_once(dir)?;
+                }
+                Ok(Box::new(store) as Box<dyn GameStore>)
+            }),
+        Err(_) => JsonDirStore::open(games_dir.unwrap_or("./games/".to_string()))
+            .map(|store| Box::new(store) as Box<dyn GameStore>),
+    };
+    let store = store
         .unwrap_or_else(|e| {
             eprintln!("{}", e);
             std::process::exit(1);
-        }).map(|r| {
-            r.map_err(|e| format!("{}", e))
-                .and_then(|e| {
-                    std::fs::read_to_string(e.path())
-                        .map_err(|e| format!("{}", e))
-                        .and_then(|s| serde_json::from_str(&s).map_err(|e| format!("{}", e)))
-                })
-        }).collect::<Result<Vec<Game>, _>>()
+        });
+
+    let owners: HashSet<UserId> = match Http::new_with_token(&token).get_current_application_info().await {
+        Ok(info) => match info.team {
+            Some(team) => team.members.into_iter().map(|m| m.user.id).collect(),
+            None => vec![info.owner.id].into_iter().collect(),
+        },
+        Err(e) => {
+            log::warn!("Couldn't find out who owns the bot, nobody can moderate or claim games: {}", e);
+            HashSet::new()
+        },
+    };
+
+    let home_guild = std::env::var("NANCY_HOME_GUILD")
+        .ok()
+        .map(|g| g.trim().parse::<u64>().map(GuildId).unwrap_or_else(|e| {
+            eprintln!("NANCY_HOME_GUILD: {}", e);
+            std::process::exit(1);
+        }));
+
+    let data_dir = std::env::var("NANCY_DATA")
+        .unwrap_or("./data/".to_string());
+    let state = std::fs::create_dir_all(&data_dir)
+        .map_err(Error::from)
+        .and_then(|()| {
+            State::new(store)
+                .with_exhausted_policy(exhausted_policy)
+                .with_owners(owners)
+                .with_home_guild(home_guild)
+                .with_snapshot(Path::new(&data_dir).join("sessions.json"))
+        })
+        .and_then(|s| s.with_guilds(Path::new(&data_dir).join("guilds.json")))
+        .and_then(|s| s.with_scores(Path::new(&data_dir).join("scores.json")))
+        .and_then(|s| s.with_matches(Path::new(&data_dir).join("matches.json")))
+        .and_then(|s| s.with_images(Path::new(&data_dir).join("images")))
         .unwrap_or_else(|e| {
             eprintln!("{}", e);
             std::process::exit(1);
@@ -373,13 +2089,13 @@ async fn main() {
         .configure(|c| c.prefix("!")
                    .delimiter("\n"))
         .help(&MY_HELP)
-        .group(&GENERAL_GROUP);
+        .group(&GENERAL_GROUP)
+        .group(&ADMIN_GROUP);
 
     log::info!("Starting nancy...");
     Client::builder(&token)
         .type_map(TypeMap::new())
-        .type_map_insert::<State>(State::new()
-                                  .with_games(games))
+        .type_map_insert::<State>(state)
         .event_handler(Handler)
         .framework(framework)
         .await
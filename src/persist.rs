@@ -0,0 +1,62 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use rand::{
+    thread_rng,
+    Rng,
+};
+use serde::{
+    Serialize,
+    de::DeserializeOwned,
+};
+use crate::error::Error;
+
+/// Writes `contents` to a temporary file next to `path` and then renames it into place, so
+/// a crash half way through never leaves a truncated file behind
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), Error> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let file_name = path.file_name()
+        .and_then(|f| f.to_str())
+        .ok_or_else(|| Error::Io(format!("Not a file path: {}", path.display())))?;
+    let tmp = dir.join(format!(".{}.{:08x}.tmp", file_name, thread_rng().gen::<u32>()));
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&tmp)?;
+    file.write_all(contents)
+        .and_then(|()| file.sync_all())
+        .and_then(|()| fs::rename(&tmp, path))
+        .map_err(|e| {
+            let _ = fs::remove_file(&tmp);
+            Error::from(e)
+        })
+}
+
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<(), Error> {
+    let contents = serde_json::to_string_pretty(value)?;
+    write_atomic(path, contents.as_bytes())
+}
+
+/// Reads json written by `save_json`, or `None` if nothing has been written yet
+pub fn load_json<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, Error> {
+    match fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map(Some)
+            .map_err(|e| Error::Serde(format!("{}: {}", path.display(), e))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Error::from(e)),
+    }
+}
+
+/// A fresh game id, the submission time in milliseconds plus a bit of noise in case two
+/// games come in at once
+pub fn new_game_id() -> String {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    format!("{:x}-{:04x}", millis, thread_rng().gen::<u16>())
+}
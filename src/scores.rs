@@ -0,0 +1,213 @@
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fmt::{Formatter, Display, self},
+};
+use serde::{Serialize, Deserialize};
+use serenity::model::id::UserId;
+use crate::{
+    games::game::GameType,
+    error::Error,
+};
+
+/// Points for a right answer, indexed by how many clues were showing when it was given
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all="kebab-case")]
+pub struct Scoring {
+    pub link: Vec<u32>,
+    pub sequence: Vec<u32>,
+}
+
+impl Default for Scoring {
+    /// Only Connect's 5/3/2/1
+    fn default() -> Scoring {
+        Scoring {
+            link: vec![5, 3, 2, 1],
+            sequence: vec![5, 3, 2, 1],
+        }
+    }
+}
+
+impl Scoring {
+    pub fn table(&self, game: &GameType) -> &[u32] {
+        match game {
+            GameType::Link(_) => &self.link,
+            GameType::Sequence(_) => &self.sequence,
+            // Walls score a point a group and a point a connection, and missing vowels a point
+            // a phrase, however long it took
+            GameType::Wall(_) | GameType::MissingVowels(_) => &[1],
+        }
+    }
+
+    /// What a right answer is worth with `clues_shown` clues showing, anything past the end of
+    /// `table` gets the last entry
+    pub fn points(table: &[u32], clues_shown: usize) -> u32 {
+        table
+            .get(clues_shown.max(1) - 1)
+            .or_else(|| table.last())
+            .copied()
+            .unwrap_or(0)
+    }
+
+    pub fn table_mut(&mut self, game_type: &str) -> Result<&mut Vec<u32>, Error> {
+        match game_type {
+            "link" => Ok(&mut self.link),
+            "sequence" | "seq" => Ok(&mut self.sequence),
+            _ => Err(Error::ArgError(format!("`{}` should be link or sequence", game_type))),
+        }
+    }
+
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all="kebab-case")]
+pub struct PlayerScore {
+    /// Their name when they last scored, so the table can be shown without looking everyone up
+    pub name: String,
+    pub points: u32,
+    #[serde(default)]
+    pub solves: u32,
+    /// Solves with only the first clue showing
+    #[serde(default)]
+    pub first_clue_solves: u32,
+    /// Clues showing, added up over every solve
+    #[serde(default)]
+    pub clues_needed: u32,
+}
+
+impl PlayerScore {
+    pub fn average_clues(&self) -> f64 {
+        if self.solves == 0 {
+            0.0
+        } else {
+            self.clues_needed as f64 / self.solves as f64
+        }
+    }
+}
+
+impl Display for PlayerScore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} - {} points ({} solves, {} from the first clue, {:.1} clues on average)",
+               self.name, self.points, self.solves, self.first_clue_solves, self.average_clues())
+    }
+}
+
+/// A finished season, kept around after `!season-reset`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all="kebab-case")]
+pub struct Season {
+    pub number: u32,
+    pub started: Option<u64>,
+    pub ended: u64,
+    pub players: HashMap<UserId, PlayerScore>,
+}
+
+/// Everyone's points in one guild for the current season, plus the seasons before it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all="kebab-case")]
+pub struct ScoreBoard {
+    #[serde(default="first_season")]
+    pub season: u32,
+    /// Unix timestamp (seconds) of the last `!season-reset`
+    #[serde(default)]
+    pub started: Option<u64>,
+    #[serde(default)]
+    pub players: HashMap<UserId, PlayerScore>,
+    #[serde(default)]
+    pub archive: Vec<Season>,
+}
+
+fn first_season() -> u32 {
+    1
+}
+
+impl Default for ScoreBoard {
+    fn default() -> ScoreBoard {
+        ScoreBoard {
+            season: first_season(),
+            started: None,
+            players: HashMap::new(),
+            archive: vec![],
+        }
+    }
+}
+
+impl ScoreBoard {
+    pub fn award(&mut self, user_id: UserId, name: &str, points: u32, clues_shown: usize) -> &PlayerScore {
+        let player = self.players
+            .entry(user_id)
+            .or_insert_with(PlayerScore::default);
+        player.name = name.to_string();
+        player.points += points;
+        player.solves += 1;
+        player.clues_needed += clues_shown as u32;
+        if clues_shown <= 1 {
+            player.first_clue_solves += 1;
+        }
+        player
+    }
+
+    /// Points that don't come from solving a round outright, eg. finding a group on a wall
+    pub fn add_points(&mut self, user_id: UserId, name: &str, points: u32) -> &PlayerScore {
+        let player = self.players
+            .entry(user_id)
+            .or_insert_with(PlayerScore::default);
+        player.name = name.to_string();
+        player.points += points;
+        player
+    }
+
+    /// The current season's players, best first
+    pub fn leaderboard(&self) -> Vec<(UserId, &PlayerScore)> {
+        ranked(&self.players)
+    }
+
+    pub fn past_season(&self, number: u32) -> Option<&Season> {
+        self.archive
+            .iter()
+            .find(|s| s.number == number)
+    }
+
+    /// Archives the current season and starts the next one with everyone on zero
+    pub fn reset_season(&mut self, now: u64) -> &Season {
+        let players = std::mem::take(&mut self.players);
+        self.archive.push(Season {
+            number: self.season,
+            started: self.started,
+            ended: now,
+            players,
+        });
+        self.season += 1;
+        self.started = Some(now);
+        &self.archive[self.archive.len() - 1]
+    }
+}
+
+/// Most points first, then most solves, then fewest clues needed on average
+pub fn ranked(players: &HashMap<UserId, PlayerScore>) -> Vec<(UserId, &PlayerScore)> {
+    let mut ranked = players
+        .iter()
+        .map(|(u, p)| (*u, p))
+        .collect::<Vec<(UserId, &PlayerScore)>>();
+    ranked.sort_by(|(_, a), (_, b)| {
+        b.points.cmp(&a.points)
+            .then(b.solves.cmp(&a.solves))
+            .then(a.average_clues().partial_cmp(&b.average_clues()).unwrap_or(Ordering::Equal))
+    });
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_go_by_clues_shown() {
+        let table = Scoring::default().link;
+        assert_eq!(Scoring::points(&table, 1), 5);
+        assert_eq!(Scoring::points(&table, 4), 1);
+        assert_eq!(Scoring::points(&table, 6), 1);
+        assert_eq!(Scoring::points(&table, 0), 5);
+        assert_eq!(Scoring::points(&[], 2), 0);
+    }
+}
@@ -0,0 +1,55 @@
+use serde::{Serialize, Deserialize};
+use serenity::model::{
+    channel::Message,
+    id::{
+        ChannelId,
+        GuildId,
+        MessageId,
+    },
+};
+use crate::{
+    games::game::PlayingGame,
+    data::unix_now,
+};
+
+/// Identifies where a round is being played, each channel gets its own round
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all="kebab-case")]
+pub struct SessionKey {
+    pub guild_id: Option<GuildId>,
+    pub channel_id: ChannelId,
+}
+
+impl From<&Message> for SessionKey {
+    fn from(msg: &Message) -> SessionKey {
+        SessionKey {
+            guild_id: msg.guild_id,
+            channel_id: msg.channel_id,
+        }
+    }
+}
+
+/// The round being played in one channel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all="kebab-case")]
+pub struct Session {
+    pub key: SessionKey,
+    pub playing: PlayingGame,
+    /// The message showing the round, which gets edited with each clue and then the answer
+    #[serde(default, skip_serializing_if="Option::is_none")]
+    pub message_id: Option<MessageId>,
+    /// Unix timestamp (seconds) of the last time anyone did anything in the round
+    #[serde(default="unix_now")]
+    pub last_active: u64,
+}
+
+impl Session {
+    pub fn new(key: SessionKey, playing: PlayingGame) -> Session {
+        Session {
+            key,
+            playing,
+            message_id: None,
+            last_active: unix_now(),
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::{
+    fs,
+    path::PathBuf,
+};
+use crate::{
+    games::game::{
+        Game,
+        GameType,
+        Review,
+    },
+    error::Error,
+    persist::{
+        write_atomic,
+        new_game_id,
+    },
+    store::{
+        GameStore,
+        StoreStats,
+        ExhaustedPolicy,
+        pick_from,
+    },
+};
+
+/// A directory with one json file per game, named after the game's id. Everything is kept in
+/// memory and each change rewrites just the file for that game.
+#[derive(Debug, Clone)]
+pub struct JsonDirStore {
+    path: PathBuf,
+    games: Vec<Game>,
+}
+
+impl JsonDirStore {
+    /// Loads every `.json` file in the directory. Games written before they had ids take
+    /// the file name as their id. Games that don't validate are still loaded so their
+    /// submitter can fix them, but they're logged and never picked to play.
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<JsonDirStore, Error> {
+        let path = path.into();
+        let games = fs::read_dir(&path)?
+            .map(|r| r.map(|e| e.path()).map_err(Error::from))
+            .filter(|r| match r {
+                Ok(path) => path.extension().map(|e| e == "json").unwrap_or(false),
+                Err(_) => true,
+            })
+            .map(|r| {
+                r.and_then(|path| {
+                    let contents = fs::read_to_string(&path)?;
+                    let mut game: Game = serde_json::from_str(&contents)
+                        .map_err(|e| Error::Serde(format!("{}: {}", path.display(), e)))?;
+                    if game.id.is_empty() {
+                        game.id = path.file_stem()
+                            .map(|s| s.to_string_lossy().to_string())
+                            .unwrap_or_default();
+                    }
+                    game.is_valid();
+                    Ok(game)
+                })
+            })
+            .collect::<Result<Vec<Game>, Error>>()?;
+        Ok(JsonDirStore { path, games })
+    }
+
+    fn game_path(&self, id: &str) -> PathBuf {
+        self.path.join(format!("{}.json", id))
+    }
+
+    fn position(&self, id: &str) -> Result<usize, Error> {
+        self.games
+            .iter()
+            .position(|g| g.id == id)
+            .ok_or_else(|| Error::NoSuchGame(id.to_string()))
+    }
+
+    fn save(&self, game: &Game) -> Result<(), Error> {
+        let contents = serde_json::to_string_pretty(game)?;
+        write_atomic(&self.game_path(&game.id), contents.as_bytes())
+    }
+}
+
+impl GameStore for JsonDirStore {
+    fn add(&mut self, game: Game) -> Result<Game, Error> {
+        let mut game = game;
+        if game.id.is_empty() {
+            game.id = loop {
+                let id = new_game_id();
+                if !self.game_path(&id).exists() {
+                    break id;
+                }
+            };
+        }
+        self.save(&game)?;
+        log::info!("Saved game to {}", self.game_path(&game.id).display());
+        self.games.push(game.clone());
+        Ok(game)
+    }
+
+    fn update(&mut self, game: Game) -> Result<Game, Error> {
+        let index = self.position(&game.id)?;
+        let game = Game {
+            plays: self.games[index].plays,
+            last_played: self.games[index].last_played,
+            ..game
+        };
+        self.save(&game)?;
+        self.games[index] = game.clone();
+        Ok(game)
+    }
+
+    fn remove(&mut self, id: &str) -> Result<Game, Error> {
+        let index = self.position(id)?;
+        fs::remove_file(self.game_path(id))?;
+        Ok(self.games.remove(index))
+    }
+
+    fn get(&self, id: &str) -> Result<Game, Error> {
+        self.position(id)
+            .map(|i| self.games[i].clone())
+    }
+
+    fn list(&self) -> Result<Vec<Game>, Error> {
+        Ok(self.games.clone())
+    }
+
+    fn stats(&self) -> Result<StoreStats, Error> {
+        Ok(self.games
+            .iter()
+            .fold(StoreStats::default(), |stats, g| StoreStats {
+                total: stats.total + 1,
+                unplayed: stats.unplayed + g.is_unplayed() as usize,
+                links: stats.links + matches!(g.game, GameType::Link(_)) as usize,
+                sequences: stats.sequences + matches!(g.game, GameType::Sequence(_)) as usize,
+                walls: stats.walls + matches!(g.game, GameType::Wall(_)) as usize,
+                missing_vowels: stats.missing_vowels + matches!(g.game, GameType::MissingVowels(_)) as usize,
+                pending: stats.pending + (g.review == Review::Pending) as usize,
+            }))
+    }
+
+    fn pick_next(&self, policy: ExhaustedPolicy, moderated: bool) -> Result<Game, Error> {
+        pick_from(&self.games, policy, moderated).map(Game::clone)
+    }
+
+    fn mark_played(&mut self, id: &str, at: u64) -> Result<Game, Error> {
+        let index = self.position(id)?;
+        let mut game = self.games[index].clone();
+        game.plays += 1;
+        game.last_played = Some(at);
+        self.save(&game)?;
+        self.games[index] = game.clone();
+        Ok(game)
+    }
+
+    fn unmark_played(&mut self, id: &str) -> Result<Game, Error> {
+        let index = self.position(id)?;
+        let mut game = self.games[index].clone();
+        game.plays = game.plays.saturating_sub(1);
+        if game.plays == 0 {
+            game.last_played = None;
+        }
+        self.save(&game)?;
+        self.games[index] = game.clone();
+        Ok(game)
+    }
+}
@@ -0,0 +1,107 @@
+pub mod json;
+pub mod sqlite;
+
+use std::str::FromStr;
+use rand::{
+    thread_rng,
+    seq::SliceRandom,
+};
+use crate::{
+    games::game::Game,
+    error::Error,
+};
+
+pub use json::JsonDirStore;
+pub use sqlite::SqliteStore;
+
+/// Somewhere to keep the games between restarts
+pub trait GameStore: std::fmt::Debug + Send + Sync {
+    /// Saves a new game, giving it an id if it doesn't already have one
+    fn add(&mut self, game: Game) -> Result<Game, Error>;
+
+    /// Replaces the game with the same id, keeping its play count and last played time
+    fn update(&mut self, game: Game) -> Result<Game, Error>;
+
+    fn remove(&mut self, id: &str) -> Result<Game, Error>;
+
+    fn get(&self, id: &str) -> Result<Game, Error>;
+
+    fn list(&self) -> Result<Vec<Game>, Error>;
+
+    fn stats(&self) -> Result<StoreStats, Error>;
+
+    /// Chooses the game for the next round, preferring ones that haven't been played. Only
+    /// approved games are picked if `moderated`, otherwise pending ones can be too. Games that
+    /// don't validate are never picked.
+    fn pick_next(&self, policy: ExhaustedPolicy, moderated: bool) -> Result<Game, Error>;
+
+    /// Bumps the play count and sets the last played time (unix seconds)
+    fn mark_played(&mut self, id: &str, at: u64) -> Result<Game, Error>;
+
+    /// Takes back a `mark_played` for a round that was abandoned, a game that goes back to no
+    /// plays forgets when it was played too
+    fn unmark_played(&mut self, id: &str) -> Result<Game, Error>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct StoreStats {
+    pub total: usize,
+    pub unplayed: usize,
+    pub links: usize,
+    pub sequences: usize,
+    pub walls: usize,
+    pub missing_vowels: usize,
+    /// Waiting for a moderator
+    pub pending: usize,
+}
+
+/// What `pick_next` does once every game has been played at least once
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExhaustedPolicy {
+    /// Play the game that was played longest ago
+    Recycle,
+    /// Don't play anything until more games are added
+    Refuse,
+    /// Don't play anything until an admin runs `!recycle`
+    AskAdmin,
+}
+
+impl FromStr for ExhaustedPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<ExhaustedPolicy, Error> {
+        match s {
+            "recycle" => Ok(ExhaustedPolicy::Recycle),
+            "refuse" => Ok(ExhaustedPolicy::Refuse),
+            "ask-admin" => Ok(ExhaustedPolicy::AskAdmin),
+            _ => Err(Error::ArgError(format!("`{}` should be one of recycle, refuse or ask-admin", s))),
+        }
+    }
+}
+
+/// `pick_next` for stores that keep every game in memory. Games that don't validate are left
+/// out.
+pub fn pick_from(games: &[Game], policy: ExhaustedPolicy, moderated: bool) -> Result<&Game, Error> {
+    let mut rng = thread_rng();
+    let games = games
+        .iter()
+        .filter(|g| g.review.is_playable(moderated) && g.is_valid())
+        .collect::<Vec<&Game>>();
+    let unplayed = games
+        .iter()
+        .copied()
+        .filter(|g| g.is_unplayed())
+        .collect::<Vec<&Game>>();
+    if let Some(game) = unplayed.choose(&mut rng) {
+        return Ok(game);
+    }
+    match policy {
+        _ if games.is_empty() => Err(Error::NoGamesLeft),
+        ExhaustedPolicy::Recycle => games
+            .into_iter()
+            .min_by_key(|g| g.last_played)
+            .ok_or(Error::NoGamesLeft),
+        ExhaustedPolicy::Refuse => Err(Error::NoGamesLeft),
+        ExhaustedPolicy::AskAdmin => Err(Error::AllGamesPlayed),
+    }
+}
@@ -0,0 +1,304 @@
+use std::{
+    path::Path,
+    sync::{Mutex, MutexGuard},
+};
+use rusqlite::{
+    params,
+    Connection,
+    OptionalExtension,
+    Row,
+};
+use crate::{
+    games::game::{
+        Game,
+        GameType,
+    },
+    error::Error,
+    persist::new_game_id,
+    store::{
+        JsonDirStore,
+        GameStore,
+        StoreStats,
+        ExhaustedPolicy,
+    },
+};
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS games (
+    id TEXT PRIMARY KEY NOT NULL,
+    submitted_by TEXT NOT NULL,
+    game_type TEXT NOT NULL,
+    data TEXT NOT NULL,
+    plays INTEGER NOT NULL DEFAULT 0,
+    last_played INTEGER,
+    review TEXT NOT NULL DEFAULT 'approved'
+);
+CREATE INDEX IF NOT EXISTS games_by_plays ON games (plays, last_played);
+CREATE TABLE IF NOT EXISTS meta (
+    key TEXT PRIMARY KEY NOT NULL,
+    value TEXT NOT NULL
+);
+"#;
+
+/// Databases made before the moderation queue don't have a `review` column, everything in them
+/// is approved
+const ADD_REVIEW: &str = "ALTER TABLE games ADD COLUMN review TEXT NOT NULL DEFAULT 'approved'";
+
+const SELECT: &str = "SELECT data, plays, last_played FROM games";
+
+/// Games kept in an embedded sqlite database. The whole game is stored as json in `data`,
+/// with the fields we query on pulled out into their own columns.
+#[derive(Debug)]
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<SqliteStore, Error> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        let has_review: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('games') WHERE name = 'review'",
+            params![],
+            |row| row.get(0))?;
+        if has_review == 0 {
+            conn.execute_batch(ADD_REVIEW)?;
+        }
+        let store = SqliteStore { conn: Mutex::new(conn) };
+        // Games that don't validate stay in the database so their submitter can fix them, but
+        // they're never picked to play
+        for game in store.list()? {
+            game.is_valid();
+        }
+        Ok(store)
+    }
+
+    fn conn(&self) -> Result<MutexGuard<Connection>, Error> {
+        self.conn
+            .lock()
+            .map_err(|_| Error::Database("connection lock was poisoned".to_string()))
+    }
+
+    fn game_type(game: &Game) -> &'static str {
+        match game.game {
+            GameType::Link(_) => "link",
+            GameType::Sequence(_) => "sequence",
+            GameType::Wall(_) => "wall",
+            GameType::MissingVowels(_) => "missing-vowels",
+        }
+    }
+
+    /// Reads a row selected with `SELECT`, the play columns win over whatever is in `data`
+    fn from_row(row: &Row) -> rusqlite::Result<Result<Game, Error>> {
+        let data: String = row.get(0)?;
+        let plays: i64 = row.get(1)?;
+        let last_played: Option<i64> = row.get(2)?;
+        Ok(serde_json::from_str::<Game>(&data)
+            .map_err(Error::from)
+            .map(|game| Game {
+                plays: plays as u32,
+                last_played: last_played.map(|t| t as u64),
+                ..game
+            }))
+    }
+
+    fn insert(conn: &Connection, game: &Game) -> Result<(), Error> {
+        let data = serde_json::to_string(game)?;
+        conn.execute(
+            "INSERT INTO games (id, submitted_by, game_type, data, plays, last_played, review) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                game.id,
+                game.submitted_by,
+                SqliteStore::game_type(game),
+                data,
+                game.plays as i64,
+                game.last_played.map(|t| t as i64),
+                game.review.name(),
+            ])?;
+        Ok(())
+    }
+
+    /// Copies the games from a json directory into a new database. This only ever happens once,
+    /// so games deleted since don't come back on the next restart. Databases that already have
+    /// games in them were filled before this was tracked, so they're left as they are. A
+    /// directory that isn't there is just nothing to import.
+    pub fn import_once<P: AsRef<Path>>(&mut self, dir: P) -> Result<usize, Error> {
+        let dir = dir.as_ref();
+        if !dir.is_dir() {
+            return Ok(0);
+        }
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let imported: Option<String> = tx.query_row(
+            "SELECT value FROM meta WHERE key = 'imported'",
+            params![],
+            |row| row.get(0))
+            .optional()?;
+        if imported.is_some() {
+            return Ok(0);
+        }
+        let count: i64 = tx.query_row("SELECT COUNT(*) FROM games", params![], |row| row.get(0))?;
+        let games = if count == 0 {
+            JsonDirStore::open(dir)?.list()?
+        } else {
+            vec![]
+        };
+        for game in &games {
+            log::info!("Importing game {} from {}", game.id, dir.display());
+            SqliteStore::insert(&tx, game)?;
+        }
+        tx.execute(
+            "INSERT INTO meta (key, value) VALUES ('imported', ?1)",
+            params![dir.display().to_string()])?;
+        tx.commit()?;
+        Ok(games.len())
+    }
+
+    /// The first game `sql` selects that's fit to play, skipping any that don't validate
+    fn first_valid(conn: &Connection, sql: &str) -> Result<Option<Game>, Error> {
+        let mut stmt = conn.prepare(sql)?;
+        let mut rows = stmt.query(params![])?;
+        while let Some(row) = rows.next()? {
+            let game = SqliteStore::from_row(row)??;
+            if game.is_valid() {
+                return Ok(Some(game));
+            }
+        }
+        Ok(None)
+    }
+
+    fn query_one(conn: &Connection, sql: &str, id: Option<&str>) -> Result<Option<Game>, Error> {
+        let row = match id {
+            Some(id) => conn.query_row(sql, params![id], SqliteStore::from_row),
+            None => conn.query_row(sql, params![], SqliteStore::from_row),
+        };
+        row.optional()?
+            .transpose()
+    }
+}
+
+impl GameStore for SqliteStore {
+    fn add(&mut self, game: Game) -> Result<Game, Error> {
+        let mut game = game;
+        if game.id.is_empty() {
+            game.id = new_game_id();
+        }
+        SqliteStore::insert(&*self.conn()?, &game)?;
+        Ok(game)
+    }
+
+    fn update(&mut self, game: Game) -> Result<Game, Error> {
+        let data = serde_json::to_string(&game)?;
+        let updated = self.conn()?.execute(
+            "UPDATE games SET submitted_by = ?2, game_type = ?3, data = ?4, review = ?5 WHERE id = ?1",
+            params![
+                game.id,
+                game.submitted_by,
+                SqliteStore::game_type(&game),
+                data,
+                game.review.name(),
+            ])?;
+        if updated == 0 {
+            return Err(Error::NoSuchGame(game.id));
+        }
+        self.get(&game.id)
+    }
+
+    fn remove(&mut self, id: &str) -> Result<Game, Error> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let game = SqliteStore::query_one(&tx, &format!("{} WHERE id = ?1", SELECT), Some(id))?
+            .ok_or_else(|| Error::NoSuchGame(id.to_string()))?;
+        tx.execute("DELETE FROM games WHERE id = ?1", params![id])?;
+        tx.commit()?;
+        Ok(game)
+    }
+
+    fn get(&self, id: &str) -> Result<Game, Error> {
+        SqliteStore::query_one(&*self.conn()?, &format!("{} WHERE id = ?1", SELECT), Some(id))?
+            .ok_or_else(|| Error::NoSuchGame(id.to_string()))
+    }
+
+    fn list(&self) -> Result<Vec<Game>, Error> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!("{} ORDER BY id", SELECT))?;
+        let games = stmt.query_map(params![], SqliteStore::from_row)?
+            .collect::<rusqlite::Result<Vec<Result<Game, Error>>>>()?;
+        games.into_iter().collect()
+    }
+
+    fn stats(&self) -> Result<StoreStats, Error> {
+        self.conn()?
+            .query_row(
+                r#"SELECT
+                    COUNT(*),
+                    COALESCE(SUM(plays = 0), 0),
+                    COALESCE(SUM(game_type = 'link'), 0),
+                    COALESCE(SUM(game_type = 'sequence'), 0),
+                    COALESCE(SUM(game_type = 'wall'), 0),
+                    COALESCE(SUM(game_type = 'missing-vowels'), 0),
+                    COALESCE(SUM(review = 'pending'), 0)
+                FROM games"#,
+                params![],
+                |row| Ok(StoreStats {
+                    total: row.get::<_, i64>(0)? as usize,
+                    unplayed: row.get::<_, i64>(1)? as usize,
+                    links: row.get::<_, i64>(2)? as usize,
+                    sequences: row.get::<_, i64>(3)? as usize,
+                    walls: row.get::<_, i64>(4)? as usize,
+                    missing_vowels: row.get::<_, i64>(5)? as usize,
+                    pending: row.get::<_, i64>(6)? as usize,
+                }))
+            .map_err(Error::from)
+    }
+
+    fn pick_next(&self, policy: ExhaustedPolicy, moderated: bool) -> Result<Game, Error> {
+        let conn = self.conn()?;
+        let playable = if moderated {
+            "review = 'approved'"
+        } else {
+            "review != 'rejected'"
+        };
+        let unplayed = SqliteStore::first_valid(&conn, &format!("{} WHERE {} AND plays = 0 ORDER BY RANDOM()", SELECT, playable))?;
+        if let Some(game) = unplayed {
+            return Ok(game);
+        }
+        let oldest = SqliteStore::first_valid(&conn, &format!("{} WHERE {} ORDER BY last_played ASC", SELECT, playable))?;
+        match (policy, oldest) {
+            (_, None) => Err(Error::NoGamesLeft),
+            (ExhaustedPolicy::Recycle, Some(game)) => Ok(game),
+            (ExhaustedPolicy::Refuse, Some(_)) => Err(Error::NoGamesLeft),
+            (ExhaustedPolicy::AskAdmin, Some(_)) => Err(Error::AllGamesPlayed),
+        }
+    }
+
+    fn mark_played(&mut self, id: &str, at: u64) -> Result<Game, Error> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let updated = tx.execute(
+            "UPDATE games SET plays = plays + 1, last_played = ?2 WHERE id = ?1",
+            params![id, at as i64])?;
+        if updated == 0 {
+            return Err(Error::NoSuchGame(id.to_string()));
+        }
+        let game = SqliteStore::query_one(&tx, &format!("{} WHERE id = ?1", SELECT), Some(id))?
+            .ok_or_else(|| Error::NoSuchGame(id.to_string()))?;
+        tx.commit()?;
+        Ok(game)
+    }
+
+    fn unmark_played(&mut self, id: &str) -> Result<Game, Error> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let updated = tx.execute(
+            "UPDATE games SET plays = MAX(plays - 1, 0), last_played = CASE WHEN plays <= 1 THEN NULL ELSE last_played END WHERE id = ?1",
+            params![id])?;
+        if updated == 0 {
+            return Err(Error::NoSuchGame(id.to_string()));
+        }
+        let game = SqliteStore::query_one(&tx, &format!("{} WHERE id = ?1", SELECT), Some(id))?
+            .ok_or_else(|| Error::NoSuchGame(id.to_string()))?;
+        tx.commit()?;
+        Ok(game)
+    }
+}
@@ -0,0 +1,255 @@
+use serde::{Serialize, Deserialize};
+use serenity::model::id::UserId;
+use crate::{
+    session::SessionKey,
+    error::Error,
+};
+
+/// Only Connect has two teams, so that's how many a match has
+pub const TEAMS: usize = 2;
+
+/// What the other team gets for answering after the team on turn missed
+pub const STEAL_POINTS: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all="kebab-case")]
+pub struct Team {
+    pub name: String,
+    pub members: Vec<UserId>,
+    #[serde(default)]
+    pub score: u32,
+}
+
+/// Where the team on turn has got to
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all="kebab-case")]
+pub enum Turn {
+    /// Choosing the next game with `!pick`
+    Picking,
+    /// Answering the game they picked, nobody else can guess
+    Answering,
+    /// They missed, the other team gets one guess with all the clues showing
+    Stealing,
+}
+
+/// Two teams taking turns in one channel. It sits above the channel's round - the team on turn
+/// picks each game and answers it, and if they get it wrong the other team can steal it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all="kebab-case")]
+pub struct Match {
+    pub key: SessionKey,
+    pub teams: Vec<Team>,
+    /// Index into `teams` of the team on turn
+    pub on_turn: usize,
+    pub turn: Turn,
+    /// Teams can be joined until the match starts
+    pub started: bool,
+    pub rounds: u32,
+}
+
+impl Match {
+    pub fn new(key: SessionKey) -> Match {
+        Match {
+            key,
+            teams: vec![],
+            on_turn: 0,
+            turn: Turn::Picking,
+            started: false,
+            rounds: 0,
+        }
+    }
+
+    /// Puts `user_id` in the team called `name`, taking them out of the other one. The team is
+    /// made if there's still room for it.
+    pub fn join(&mut self, name: &str, user_id: UserId) -> Result<&Team, Error> {
+        if self.started {
+            return Err(Error::MatchStarted);
+        }
+        let index = match self.teams.iter().position(|t| t.name.eq_ignore_ascii_case(name)) {
+            Some(i) => i,
+            None if self.teams.len() < TEAMS => {
+                self.teams.push(Team {
+                    name: name.to_string(),
+                    members: vec![],
+                    score: 0,
+                });
+                self.teams.len() - 1
+            },
+            None => return Err(Error::ArgError(format!("There are already {} teams, join {}", TEAMS, self.team_names()))),
+        };
+        for team in self.teams.iter_mut() {
+            team.members.retain(|m| *m != user_id);
+        }
+        self.teams[index].members.push(user_id);
+        Ok(&self.teams[index])
+    }
+
+    /// Starts the match once there are two teams with someone in each
+    pub fn start(&mut self) -> Result<(), Error> {
+        if self.started {
+            return Err(Error::MatchStarted);
+        }
+        if self.teams.len() < TEAMS || self.teams.iter().any(|t| t.members.is_empty()) {
+            return Err(Error::TeamsNotReady);
+        }
+        self.started = true;
+        self.on_turn = 0;
+        self.turn = Turn::Picking;
+        Ok(())
+    }
+
+    pub fn team_of(&self, user_id: UserId) -> Option<usize> {
+        self.teams
+            .iter()
+            .position(|t| t.members.contains(&user_id))
+    }
+
+    /// The team that isn't on turn
+    pub fn other(&self) -> usize {
+        (self.on_turn + 1) % TEAMS
+    }
+
+    /// The team `user_id` is answering for, if it's their go - the team on turn while they're
+    /// answering, the other one when it's up for a steal
+    pub fn answering_team(&self, user_id: UserId) -> Result<usize, Error> {
+        let team = self.team_of(user_id).ok_or(Error::NotInTeam)?;
+        let up = match self.turn {
+            Turn::Stealing => self.other(),
+            _ => self.on_turn,
+        };
+        if team == up && self.turn != Turn::Picking {
+            Ok(team)
+        } else {
+            Err(Error::NotYourTurn(self.teams[up].name.clone()))
+        }
+    }
+
+    /// Only the team answering can ask for more clues
+    pub fn check_clue(&self, user_id: UserId) -> Result<(), Error> {
+        match self.turn {
+            Turn::Answering if self.team_of(user_id) != Some(self.on_turn) =>
+                Err(Error::NotYourTurn(self.teams[self.on_turn].name.clone())),
+            _ => Ok(()),
+        }
+    }
+
+    /// The team on turn missed (or ran out of time), so the other team gets a go
+    pub fn open_steal(&mut self) {
+        if self.turn == Turn::Answering {
+            self.turn = Turn::Stealing;
+        }
+    }
+
+    /// The round's over, so it's the other team's turn to pick
+    pub fn next_turn(&mut self) {
+        self.on_turn = self.other();
+        self.turn = Turn::Picking;
+        self.rounds += 1;
+    }
+
+    fn team_names(&self) -> String {
+        self.teams
+            .iter()
+            .map(|t| t.name.as_str())
+            .collect::<Vec<&str>>()
+            .join(" or ")
+    }
+
+    /// Both teams' scores, and who's up next
+    pub fn scoreboard(&self) -> String {
+        let scores = self.teams
+            .iter()
+            .map(|t| format!("{}: {}", t.name, t.score))
+            .collect::<Vec<String>>()
+            .join("\n");
+        let next = match (self.started, self.teams.get(self.on_turn)) {
+            (true, Some(team)) if self.turn == Turn::Picking => format!("\n\n{} to `!pick` the next game", team.name),
+            _ => String::new(),
+        };
+        format!("Scores after {} rounds\n```\n{}\n```{}", self.rounds, scores, next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serenity::model::id::ChannelId;
+
+    const ALICE: UserId = UserId(1);
+    const BOB: UserId = UserId(2);
+    const CAROL: UserId = UserId(3);
+
+    fn ready() -> Match {
+        let mut m = Match::new(SessionKey { guild_id: None, channel_id: ChannelId(1) });
+        m.join("Pharaohs", ALICE).unwrap();
+        m.join("Scribes", BOB).unwrap();
+        m
+    }
+
+    #[test]
+    fn needs_two_teams_to_start() {
+        let mut m = ready();
+        m.teams.pop();
+        assert!(matches!(m.start(), Err(Error::TeamsNotReady)));
+        assert!(matches!(m.join("Nomads", CAROL), Ok(_)));
+        assert!(matches!(m.join("Others", CAROL), Err(Error::ArgError(_))));
+    }
+
+    #[test]
+    fn starting_again_keeps_the_turn() {
+        let mut m = ready();
+        m.start().unwrap();
+        m.next_turn();
+        assert!(matches!(m.start(), Err(Error::MatchStarted)));
+        assert_eq!(m.on_turn, 1);
+        assert!(matches!(m.join("Pharaohs", CAROL), Err(Error::MatchStarted)));
+    }
+
+    #[test]
+    fn only_the_team_on_turn_answers_until_its_stolen() {
+        let mut m = ready();
+        m.start().unwrap();
+        assert!(matches!(m.answering_team(ALICE), Err(Error::NotYourTurn(_))));
+        m.turn = Turn::Answering;
+        assert_eq!(m.answering_team(ALICE).unwrap(), 0);
+        assert!(matches!(m.answering_team(BOB), Err(Error::NotYourTurn(_))));
+        assert!(matches!(m.answering_team(CAROL), Err(Error::NotInTeam)));
+        m.open_steal();
+        assert_eq!(m.turn, Turn::Stealing);
+        assert_eq!(m.answering_team(BOB).unwrap(), 1);
+        assert!(matches!(m.answering_team(ALICE), Err(Error::NotYourTurn(_))));
+    }
+
+    #[test]
+    fn only_the_team_on_turn_gets_clues() {
+        let mut m = ready();
+        m.start().unwrap();
+        m.turn = Turn::Answering;
+        assert!(m.check_clue(ALICE).is_ok());
+        assert!(matches!(m.check_clue(BOB), Err(Error::NotYourTurn(_))));
+        assert!(matches!(m.check_clue(CAROL), Err(Error::NotYourTurn(_))));
+    }
+
+    #[test]
+    fn turns_alternate() {
+        let mut m = ready();
+        m.start().unwrap();
+        m.turn = Turn::Stealing;
+        m.next_turn();
+        assert_eq!((m.on_turn, m.turn, m.rounds), (1, Turn::Picking, 1));
+        m.next_turn();
+        assert_eq!((m.on_turn, m.rounds), (0, 2));
+    }
+
+    #[test]
+    fn scoreboard_says_who_picks_next() {
+        let mut m = ready();
+        m.teams[0].score = 5;
+        assert_eq!(m.scoreboard(), "Scores after 0 rounds\n```\nPharaohs: 5\nScribes: 0\n```");
+        m.start().unwrap();
+        m.next_turn();
+        assert!(m.scoreboard().ends_with("Scribes to `!pick` the next game"));
+        m.turn = Turn::Answering;
+        assert!(m.scoreboard().ends_with("```"));
+    }
+}
@@ -0,0 +1,279 @@
+use std::time::Duration;
+use serenity::{
+    client::Context,
+    model::{
+        channel::Message,
+        id::{
+            ChannelId,
+            UserId,
+        },
+    },
+};
+use crate::{
+    games::{
+        game::GameType,
+        answer::Accepted,
+        link::{
+            self,
+            LinkGame,
+            TextLink,
+        },
+        sequence::{
+            self,
+            SequenceGame,
+            TextSequence,
+        },
+        wall::{
+            WallGame,
+            TextWall,
+            WallGroup,
+            GROUPS,
+            GROUP_SIZE,
+        },
+        vowels::{
+            MissingVowelsGame,
+            TextMissingVowels,
+        },
+    },
+    error::Error,
+};
+
+/// How long to wait for each answer before giving up
+const REPLY_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// What to send when you're finished giving clues (or phrases)
+const DONE: &str = "done";
+
+/// Asks a submitter for a game one question at a time in their DMs, using the collector to wait
+/// for each reply
+pub struct Wizard<'a> {
+    context: &'a Context,
+    channel_id: ChannelId,
+    author_id: UserId,
+}
+
+impl<'a> Wizard<'a> {
+    pub fn new(context: &'a Context, msg: &Message) -> Wizard<'a> {
+        Wizard {
+            context,
+            channel_id: msg.channel_id,
+            author_id: msg.author.id,
+        }
+    }
+
+    pub async fn say(&self, text: &str) -> Result<(), Error> {
+        self.channel_id
+            .say(&self.context.http, text)
+            .await
+            .map(|_| ())
+            .map_err(|e| Error::Io(e.to_string()))
+    }
+
+    /// Asks a question and waits for the answer, `cancel` stops the whole thing
+    pub async fn ask(&self, question: &str) -> Result<String, Error> {
+        self.say(question).await?;
+        let reply = self.channel_id
+            .await_reply(&self.context)
+            .author_id(self.author_id)
+            .timeout(REPLY_TIMEOUT)
+            .await
+            .ok_or(Error::TimedOut)?;
+        match reply.content.trim() {
+            c if c.eq_ignore_ascii_case("cancel") => Err(Error::Cancelled),
+            c => Ok(c.to_string()),
+        }
+    }
+
+    /// Keeps asking until the answer isn't blank
+    async fn ask_for(&self, question: &str) -> Result<String, Error> {
+        loop {
+            let answer = self.ask(question).await?;
+            if !answer.is_empty() {
+                return Ok(answer);
+            }
+        }
+    }
+
+    /// Collects one thing per message until the submitter says they're done, asking for at
+    /// least `min` and stopping at `max`
+    async fn ask_list(&self, thing: &str, min: usize, max: usize) -> Result<Vec<String>, Error> {
+        let mut list = vec![];
+        while list.len() < max {
+            let question = if list.len() < min {
+                format!("{} {}?", thing, list.len() + 1)
+            } else {
+                format!("{} {}? (or `{}`)", thing, list.len() + 1, DONE)
+            };
+            let answer = self.ask_for(&question).await?;
+            if list.len() >= min && answer.eq_ignore_ascii_case(DONE) {
+                break;
+            }
+            list.push(answer);
+        }
+        Ok(list)
+    }
+
+    /// Other ways of writing the answer, `none` for there aren't any
+    async fn ask_accepted(&self) -> Result<Vec<Accepted>, Error> {
+        let answer = self.ask_for("Any other ways of writing it that should count? One per line, regexes between slashes, or `none`").await?;
+        if answer.eq_ignore_ascii_case("none") {
+            return Ok(vec![]);
+        }
+        Ok(answer.lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(Accepted::parse)
+            .collect())
+    }
+
+    /// A short name for a sequence's pattern, `none` for there isn't one
+    async fn ask_pattern(&self) -> Result<Option<String>, Error> {
+        let pattern = self.ask_for("Short name for the pattern, which counts as right too (or `none`)?").await?;
+        Ok(Some(pattern).filter(|p| !p.eq_ignore_ascii_case("none")))
+    }
+
+    /// Four clues for a wall group, one per line or separated by commas
+    async fn ask_group(&self, group: usize) -> Result<Vec<String>, Error> {
+        loop {
+            let answer = self.ask_for(&format!("The {} clues in group {}? One per line or separated by commas", GROUP_SIZE, group)).await?;
+            let clues = split_list(&answer);
+            if clues.len() == GROUP_SIZE {
+                return Ok(clues);
+            }
+            self.say(&format!("That's {} clues, a group needs {}", clues.len(), GROUP_SIZE)).await?;
+        }
+    }
+
+    /// Asks for every part of a game, starting with what type it is
+    pub async fn ask_game(&self) -> Result<GameType, Error> {
+        loop {
+            let game_type = self.ask_for("What type of game? `link`, `sequence`, `wall` or `vowels` (send `cancel` at any point to stop)").await?;
+            match game_type.to_lowercase().as_str() {
+                "link" => {
+                    let clues = self.ask_list("Clue", link::MIN_CLUES, link::MAX_CLUES).await?;
+                    let answer = self.ask_for("What's the link?").await?;
+                    let accepted = self.ask_accepted().await?;
+                    return Ok(GameType::Link(LinkGame::Text(TextLink { clues, answer, accepted, images: vec![] })));
+                },
+                "sequence" | "seq" => {
+                    let clues = self.ask_list("Element", sequence::MIN_CLUES, sequence::MAX_CLUES).await?;
+                    let answer = self.ask_for("How does the sequence work?").await?;
+                    let pattern = self.ask_pattern().await?;
+                    let accepted = self.ask_accepted().await?;
+                    return Ok(GameType::Sequence(SequenceGame::Text(TextSequence { clues, answer, accepted, pattern, images: vec![] })));
+                },
+                "wall" => {
+                    let mut groups = vec![];
+                    for g in 1..=GROUPS {
+                        let clues = self.ask_group(g).await?;
+                        let connection = self.ask_for(&format!("What connects group {}?", g)).await?;
+                        groups.push(WallGroup { clues, connection, accepted: vec![] });
+                    }
+                    return Ok(GameType::Wall(WallGame::Text(TextWall { groups })));
+                },
+                "vowels" | "missing vowels" => {
+                    let category = self.ask_for("What's the category?").await?;
+                    let phrases = self.ask_list("Phrase", 1, usize::MAX).await?;
+                    return Ok(GameType::MissingVowels(MissingVowelsGame::Text(TextMissingVowels { category, phrases })));
+                },
+                other => self.say(&format!("`{}` isn't a type of game", other)).await?,
+            }
+        }
+    }
+
+    /// Asks for the new value of `field` and puts it in the game
+    pub async fn edit(&self, game: &mut GameType, field: &str) -> Result<(), Error> {
+        let (name, number) = parse_field(field);
+        let slot = |list_len: usize| number
+            .filter(|n| *n >= 1 && *n <= list_len)
+            .map(|n| n - 1)
+            .ok_or_else(|| Error::ArgError(format!("`{}` should be followed by a number from 1 to {}", name, list_len)));
+        match (game, name.as_str()) {
+            (GameType::Link(LinkGame::Text(g)), "clue") => {
+                let i = slot(g.clues.len())?;
+                g.clues[i] = self.ask_for(&format!("New clue {}?", i + 1)).await?;
+            },
+            (GameType::Link(LinkGame::Text(g)), "answer") =>
+                g.answer = self.ask_for("New answer?").await?,
+            (GameType::Link(LinkGame::Text(g)), "accepted") =>
+                g.accepted = self.ask_accepted().await?,
+            (GameType::Sequence(SequenceGame::Text(g)), "clue") | (GameType::Sequence(SequenceGame::Text(g)), "element") => {
+                let i = slot(g.clues.len())?;
+                g.clues[i] = self.ask_for(&format!("New element {}?", i + 1)).await?;
+            },
+            (GameType::Sequence(SequenceGame::Text(g)), "answer") =>
+                g.answer = self.ask_for("New explanation of the sequence?").await?,
+            (GameType::Sequence(SequenceGame::Text(g)), "pattern") =>
+                g.pattern = self.ask_pattern().await?,
+            (GameType::Sequence(SequenceGame::Text(g)), "accepted") =>
+                g.accepted = self.ask_accepted().await?,
+            (GameType::Wall(WallGame::Text(g)), "group") => {
+                let i = slot(g.groups.len())?;
+                g.groups[i].clues = self.ask_group(i + 1).await?;
+            },
+            (GameType::Wall(WallGame::Text(g)), "connection") => {
+                let i = slot(g.groups.len())?;
+                g.groups[i].connection = self.ask_for(&format!("New connection for group {}?", i + 1)).await?;
+            },
+            (GameType::MissingVowels(MissingVowelsGame::Text(g)), "category") =>
+                g.category = self.ask_for("New category?").await?,
+            (GameType::MissingVowels(MissingVowelsGame::Text(g)), "phrase") => {
+                let i = slot(g.phrases.len())?;
+                g.phrases[i] = self.ask_for(&format!("New phrase {}?", i + 1)).await?;
+            },
+            (game, _) => return Err(Error::ArgError(format!("`{}` isn't something you can edit, try {}", field, fields(game)))),
+        }
+        Ok(())
+    }
+
+    /// Shows the game and lets the submitter edit it until they're happy, giving back the game
+    /// they said yes to. It's validated first so any mistakes can be fixed in place.
+    pub async fn review(&self, mut game: GameType) -> Result<GameType, Error> {
+        loop {
+            let preview = format!("```\n{}\n```\nReply `yes` if it's right, `edit <field>` to change something ({}), or `cancel`", game, fields(&game));
+            let answer = self.ask_for(&preview).await?;
+            let lower = answer.to_lowercase();
+            if lower == "yes" || lower == "y" {
+                match game.playable().validate() {
+                    Ok(()) => return Ok(game),
+                    Err(e) => self.say(&e.to_string()).await?,
+                }
+            } else if let Some(field) = lower.strip_prefix("edit") {
+                if let Err(e) = self.edit(&mut game, field.trim()).await {
+                    match e {
+                        Error::Cancelled | Error::TimedOut => return Err(e),
+                        e => self.say(&e.to_string()).await?,
+                    }
+                }
+            } else {
+                self.say("Reply `yes`, `edit <field>` or `cancel`").await?;
+            }
+        }
+    }
+}
+
+/// Splits `"clue 2"` (or `"clue2"`) into the field name and the number
+fn parse_field(field: &str) -> (String, Option<usize>) {
+    let field = field.trim();
+    let digits = field.find(|c: char| c.is_ascii_digit()).unwrap_or(field.len());
+    let name = field[..digits].trim().to_string();
+    (name, field[digits..].trim().parse().ok())
+}
+
+/// The fields that can be edited for each type of game
+fn fields(game: &GameType) -> &'static str {
+    match game {
+        GameType::Link(_) => "`clue <n>`, `answer`, `accepted`",
+        GameType::Sequence(_) => "`element <n>`, `answer`, `pattern`, `accepted`",
+        GameType::Wall(_) => "`group <n>`, `connection <n>`",
+        GameType::MissingVowels(_) => "`category`, `phrase <n>`",
+    }
+}
+
+/// Lines, or if it's all on one line, comma separated values
+fn split_list(s: &str) -> Vec<String> {
+    let separator = if s.contains('\n') { '\n' } else { ',' };
+    s.split(separator)
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect()
+}